@@ -0,0 +1,20 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new().buffer_stdin();
+    let mut buf = String::new();
+    let mut rewound = false;
+    while diamond.read_line_replacing(&mut buf)? != 0 {
+        print!(
+            "{}: {}",
+            diamond.current_arg().unwrap().to_string_lossy(),
+            buf
+        );
+        if !rewound && diamond.is_stdin().unwrap() {
+            println!("(rewind)");
+            diamond.rewind()?;
+            rewound = true;
+        }
+    }
+    Ok(())
+}
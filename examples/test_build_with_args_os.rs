@@ -0,0 +1,12 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let args_os = env::var("ARGV").unwrap_or_default();
+    let config = diamond_op::DiamondConfig::default();
+    let mut diamond = config.build_with_args_os(args_os.split_whitespace());
+    println!("sources: {:?}", diamond.source_names());
+    while let Some(line) = diamond.next_line()? {
+        print!("{}", line);
+    }
+    Ok(())
+}
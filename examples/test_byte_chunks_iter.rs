@@ -0,0 +1,19 @@
+use std::io::{self, Write as _};
+
+fn main() -> io::Result<()> {
+    let n = std::env::var("N").map_or(64, |n| n.parse().unwrap());
+    let mut sizes = Vec::new();
+    for chunk in diamond_op::new().byte_chunks_iter(n) {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                continue;
+            }
+        };
+        sizes.push(chunk.len());
+        io::stdout().write_all(&chunk)?;
+    }
+    eprintln!("{:?}", sizes);
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use std::{env, fs, io};
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new().canonicalize_names();
+    let map = env::var_os("MAP").is_some();
+    if map {
+        diamond = diamond.map_source_name(|arg| format!("<{}>", arg.to_string_lossy()).into());
+    }
+    let mut args = env::args_os().skip(1);
+    let mut prev = None;
+    let mut buf = String::new();
+    while diamond.read_line_replacing(&mut buf)? != 0 {
+        if diamond.current_index() != prev {
+            prev = diamond.current_index();
+            let arg = args.next().unwrap();
+            let name = diamond.current_arg().unwrap();
+            if let Ok(path) = fs::canonicalize(&arg) {
+                let expected = match map {
+                    true => format!("<{}>", path.to_string_lossy()).into(),
+                    false => path.into_os_string(),
+                };
+                let is_canonical = name == expected;
+                println!("== {:?}: canonical: {}", arg, is_canonical);
+            } else {
+                println!("== {:?}: reported as {:?}", arg, name);
+            }
+        }
+        print!("{}", buf);
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use std::{env, ffi::OsString, io};
+
+fn main() -> io::Result<()> {
+    let extra = env::var("CHAIN").unwrap_or_default();
+    let extra = extra.split_whitespace();
+    let mut diamond =
+        diamond_op::new().chain(diamond_op::DiamondConfig::default().build_with_args(extra));
+    println!("sources: {:?}", diamond.source_names());
+    if env::var_os("INSPECT").is_some() {
+        let plan = diamond.plan()?;
+        println!(
+            "plan: {:?}",
+            plan.iter().map(|s| &s.arg).collect::<Vec<_>>()
+        );
+        println!("total bytes: {:?}", diamond.total_bytes()?);
+        let totals = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = totals.clone();
+        diamond = diamond.with_progress(move |p| sink.lock().unwrap().push(p.total_sources));
+        diamond.next_line()?;
+        println!("{:?}", diamond);
+        println!("total sources: {:?}", totals.lock().unwrap());
+        let (_, remaining) = diamond.into_remaining();
+        println!("remaining: {:?}", remaining);
+        return Ok(());
+    }
+    let mut lines = 0;
+    let mut prev: Option<OsString> = None;
+    while let Some(line) = diamond.next_line()? {
+        if diamond.current_arg() != prev.as_deref() {
+            prev = diamond.current_arg().map(Into::into);
+            let last = diamond.is_last_source().unwrap();
+            println!("== {:?} (last: {})", prev.as_ref().unwrap(), last);
+        }
+        print!("{}", line);
+        lines += 1;
+    }
+    println!("lines: {}, bytes: {}", lines, diamond.bytes_read());
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use std::io;
+
+fn print_lines(diamond: diamond_op::Diamond) -> io::Result<()> {
+    for line in diamond.line_iter() {
+        print!("{:?} ", line?);
+    }
+    println!();
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let diamond = diamond_op::new()
+        .head_bytes_per_file(20)
+        .yield_empty_sources();
+    let config = diamond.config();
+    print_lines(diamond)?;
+    print_lines(config.build_with_args(std::env::args_os().skip(1)))?;
+    print_lines(config.build_with_args(["b.txt"]))?;
+    Ok(())
+}
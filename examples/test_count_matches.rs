@@ -0,0 +1,16 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let needle = std::env::var("NEEDLE").unwrap_or_else(|_| "TODO".into());
+    if std::env::var_os("PER_FILE").is_some() {
+        for (arg, count) in diamond_op::new().count_matches_per_file(needle.as_bytes())? {
+            println!("{}:{}", arg.to_string_lossy(), count);
+        }
+    } else {
+        match diamond_op::new().count_matches(needle.as_bytes()) {
+            Ok(count) => println!("{}", count),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(())
+}
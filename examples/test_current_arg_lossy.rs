@@ -0,0 +1,11 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    println!("{:?}", diamond.current_arg_lossy());
+    while let Some(line) = diamond.next_line()? {
+        print!("{:?} {}", diamond.current_arg_lossy(), line);
+    }
+    println!("{:?}", diamond.current_arg_lossy());
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    assert!(diamond.current_index().is_none());
+
+    let mut buf = String::new();
+    while diamond.read_line(&mut buf)? != 0 {
+        print!("[#{}] {}", diamond.current_index().unwrap(), buf);
+        buf.clear();
+    }
+
+    assert!(diamond.current_index().is_none());
+    Ok(())
+}
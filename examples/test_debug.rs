@@ -0,0 +1,12 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    println!("{:?}", diamond);
+    let mut buf = String::new();
+    while diamond.read_line_replacing(&mut buf)? != 0 {
+        println!("{:?}", diamond);
+    }
+    println!("{:?}", diamond);
+    Ok(())
+}
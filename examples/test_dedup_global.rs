@@ -0,0 +1,17 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let lines: Vec<_> = if std::env::var_os("BY_HASH").is_some() {
+        diamond_op::new()
+            .dedup_global_by_hash()
+            .collect::<io::Result<_>>()?
+    } else {
+        diamond_op::new()
+            .dedup_global()
+            .collect::<io::Result<_>>()?
+    };
+    for line in lines {
+        print!("{}", line);
+    }
+    Ok(())
+}
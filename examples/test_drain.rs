@@ -0,0 +1,11 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    let mut buf = String::new();
+    diamond.read_line(&mut buf)?;
+    print!("{}", buf);
+    println!("drained {} bytes", diamond.drain()?);
+    assert_eq!(diamond.read_line(&mut buf)?, 0);
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    if let Ok(prefix) = env::var("SKIP_PREFIX") {
+        diamond = diamond.skip_while_line(move |line| line.starts_with(&prefix));
+    }
+    if env::var_os("HEADER").is_some() {
+        diamond = diamond.first_source_callback(|reader| {
+            let mut header = String::new();
+            reader.read_line(&mut header).map(drop)
+        });
+    }
+    while let Some(line) = diamond.next_line()? {
+        print!("{}", line);
+    }
+    for arg in diamond.empty_sources() {
+        println!("empty: {}", arg.to_string_lossy());
+    }
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use diamond_op::DiamondError;
+
+fn main() {
+    for line in diamond_op::new().line_iter() {
+        match line {
+            Ok(line) => print!("{}", line),
+            Err(e) => {
+                let err = DiamondError::get(&e).expect("unwrapped error");
+                println!("[{:?} {:?}] {}", err.phase(), e.kind(), e);
+            }
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use diamond_op::DiamondError;
+use std::io::{self, ErrorKind};
+
+fn main() {
+    let kinds = [
+        ErrorKind::NotFound,
+        ErrorKind::PermissionDenied,
+        ErrorKind::BrokenPipe,
+        ErrorKind::InvalidData,
+        ErrorKind::InvalidInput,
+        ErrorKind::UnexpectedEof,
+        ErrorKind::Other,
+    ];
+    for kind in kinds {
+        let code = DiamondError::exit_code_for(&io::Error::from(kind));
+        println!("{:?}: {}", kind, code);
+    }
+    for arg in std::env::args().skip(1) {
+        let diamond = diamond_op::DiamondConfig::default().build_with_args([&arg]);
+        let code = match diamond.line_iter().find_map(Result::err) {
+            Some(e) => DiamondError::exit_code_for(&e),
+            None => 0,
+        };
+        println!("{}: {}", arg, code);
+    }
+}
@@ -0,0 +1,14 @@
+use std::io::{self, Write as _};
+
+fn main() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    for item in diamond_op::new().file_bytes_iter() {
+        let (arg, content) = item?;
+        if std::env::var_os("NAMES").is_some() {
+            writeln!(stdout, "{}: {} bytes", arg.to_string_lossy(), content.len())?;
+        } else {
+            stdout.write_all(&content)?;
+        }
+    }
+    Ok(())
+}
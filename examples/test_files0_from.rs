@@ -0,0 +1,21 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let source = std::env::args_os().nth(1).expect("list file expected");
+    let mut diamond = match diamond_op::new().files0_from(source) {
+        Ok(diamond) => diamond,
+        Err(e) => {
+            println!("error: {}", e);
+            return Ok(());
+        }
+    };
+    let mut buf = String::new();
+    while diamond.read_line_replacing(&mut buf)? != 0 {
+        print!(
+            "{}: {}",
+            diamond.current_arg().unwrap().to_string_lossy(),
+            buf
+        );
+    }
+    Ok(())
+}
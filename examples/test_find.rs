@@ -0,0 +1,30 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let needle = std::env::var("NEEDLE").unwrap_or_else(|_| "TODO".into());
+    let mut diamond = diamond_op::new();
+    loop {
+        match diamond.find(needle.as_bytes()) {
+            Ok(Some(offset)) => {
+                let arg = diamond
+                    .current_arg()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                println!("found at {} (now in {})", offset, arg);
+                if std::env::var_os("ONCE").is_some() {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                println!("error: {}", e);
+                break;
+            }
+        }
+    }
+    let mut buf = String::new();
+    diamond.read_line(&mut buf)?;
+    println!("rest: {:?}", buf);
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    if std::env::var_os("YIELD_EMPTY").is_some() {
+        diamond = diamond.yield_empty_sources();
+    }
+    for item in diamond.first_line_per_file_iter() {
+        let (arg, line) = item?;
+        println!("{}: {:?}", arg.to_string_lossy(), line);
+    }
+    Ok(())
+}
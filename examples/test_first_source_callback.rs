@@ -0,0 +1,23 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+
+fn main() -> io::Result<()> {
+    let header = Arc::new(Mutex::new(String::new()));
+    let mut diamond = diamond_op::new().first_source_callback({
+        let header = Arc::clone(&header);
+        move |reader| reader.read_line(&mut header.lock().unwrap()).map(drop)
+    });
+    while let Some(line) = diamond.next_line()? {
+        print!(
+            "{}: {}",
+            diamond.current_arg().unwrap().to_string_lossy(),
+            line
+        );
+    }
+    print!("header: {}", header.lock().unwrap());
+    if std::env::var_os("COUNT").is_some() {
+        println!("bytes read: {}", diamond.bytes_read());
+        println!("empty sources: {:?}", diamond.empty_sources());
+    }
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::{collections::BTreeMap, env, io};
+
+fn main() -> io::Result<()> {
+    let stop_at = env::var("STOP_AT").ok();
+    let mut diamond = diamond_op::new();
+    let result = diamond.fold_lines(BTreeMap::new(), |mut lens, arg, line| {
+        if stop_at.as_deref() == Some(line.trim_end()) {
+            return Err(io::Error::other("stopped"));
+        }
+        *lens.entry(arg.to_owned()).or_insert(0) += line.len();
+        Ok(lens)
+    });
+    match result {
+        Ok(lens) => {
+            for (arg, len) in lens {
+                println!("{}: {}", arg.to_string_lossy(), len);
+            }
+        }
+        Err(e) => println!("error: {}", e),
+    }
+    println!("bytes read: {}", diamond.bytes_read());
+    Ok(())
+}
@@ -0,0 +1,9 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let list = env::args_os().nth(1).expect("no list file given");
+    for line in diamond_op::from_list_file(list)?.line_iter() {
+        print!("{}", line?);
+    }
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use std::io::{self, BufRead, Cursor};
+
+fn main() -> io::Result<()> {
+    let sources: Vec<(std::ffi::OsString, Box<dyn BufRead + Send>)> = vec![
+        ("first".into(), Box::new(Cursor::new("one\ntwo"))),
+        ("empty".into(), Box::new(io::empty())),
+        (
+            "third".into(),
+            Box::new(Cursor::new(b"three\n\xff\n".to_vec())),
+        ),
+    ];
+    let mut diamond = diamond_op::from_named_readers(sources).yield_empty_sources();
+    while let Some(record) = diamond.next_record(b'\n')? {
+        let arg = diamond.current_arg().unwrap().to_string_lossy();
+        println!("{}: {:?}", arg, String::from_utf8_lossy(&record));
+    }
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let pattern = std::env::var("PATTERN").unwrap();
+    let before = std::env::var("BEFORE").map_or(1, |n| n.parse().unwrap());
+    let after = std::env::var("AFTER").map_or(1, |n| n.parse().unwrap());
+    let diamond = diamond_op::new();
+    for line in diamond.grep_context(|line| line.contains(&pattern), before, after) {
+        let line = line?;
+        let sep = if line.is_match { ':' } else { '-' };
+        print!(
+            "{}{}{}{}{}",
+            line.source.to_string_lossy(),
+            sep,
+            line.line_number,
+            sep,
+            line.line
+        );
+    }
+    Ok(())
+}
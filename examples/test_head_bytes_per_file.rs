@@ -0,0 +1,8 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    for line in diamond_op::new().head_bytes_per_file(5).line_iter() {
+        println!("{:?}", line?);
+    }
+    Ok(())
+}
@@ -0,0 +1,9 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    for line in diamond_op::new().indexed_line_iter() {
+        let (i, line) = line?;
+        print!("[{}] {}", i, line);
+    }
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use std::io;
+use std::sync::{Arc, atomic::AtomicBool, atomic::Ordering};
+
+fn main() -> io::Result<()> {
+    let limit: usize = std::env::var("STOP_AFTER").unwrap().parse().unwrap();
+    let flag = Arc::new(AtomicBool::new(false));
+    let mut diamond = diamond_op::new().interruptible(Arc::clone(&flag));
+    let mut count = 0;
+    while let Some(line) = diamond.next_line()? {
+        print!("{}", line);
+        count += 1;
+        if count == limit {
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+    println!("stopped after {} lines", count);
+    Ok(())
+}
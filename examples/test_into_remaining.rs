@@ -0,0 +1,18 @@
+use std::io::{self, Write as _};
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    print!("read: {}", diamond.next_line()?.unwrap_or_default());
+    let (buffered, remaining) = diamond.into_remaining();
+    match buffered {
+        Some(buffered) => {
+            println!("buffered:");
+            io::stdout().write_all(&buffered)?;
+        }
+        None => println!("buffered: none"),
+    }
+    for arg in remaining {
+        println!("remaining: {}", arg.to_string_lossy());
+    }
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    println!("before: {:?}", diamond.is_last_source());
+    let mut buf = String::new();
+    while diamond.read_line_replacing(&mut buf)? != 0 {
+        print!("[{:?}] {}", diamond.is_last_source().unwrap(), buf);
+    }
+    println!("after: {:?}", diamond.is_last_source());
+    Ok(())
+}
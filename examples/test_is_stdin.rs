@@ -0,0 +1,20 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    assert!(diamond.is_stdin().is_none());
+
+    let mut buf = String::new();
+    while diamond.read_line(&mut buf)? != 0 {
+        let label = if diamond.is_stdin().unwrap() {
+            "stdin"
+        } else {
+            "file"
+        };
+        print!("[{}] {}", label, buf);
+        buf.clear();
+    }
+
+    assert!(diamond.is_stdin().is_none());
+    Ok(())
+}
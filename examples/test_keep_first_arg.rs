@@ -0,0 +1,14 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new().keep_first_arg();
+    let mut buf = String::new();
+    while diamond.read_line_replacing(&mut buf)? != 0 {
+        print!(
+            "{}: {}",
+            diamond.current_arg().unwrap().to_string_lossy(),
+            buf
+        );
+    }
+    Ok(())
+}
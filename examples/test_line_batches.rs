@@ -0,0 +1,15 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let n = std::env::var("N").map_or(3, |n| n.parse().unwrap());
+    let cross = std::env::var_os("CROSS").is_some();
+    let diamond = diamond_op::new().line_batches_cross_files(cross);
+    for batch in diamond.line_batches(n) {
+        let batch = batch?;
+        println!("-- {} lines", batch.len());
+        for line in batch {
+            print!("{}", line);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,8 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    for (arg, stats) in diamond_op::new().line_ending_report()? {
+        println!("{}: {:?}", arg.to_string_lossy(), stats);
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new().map_source_name(|arg| {
+        if arg == "-" {
+            "(stdin)".into()
+        } else {
+            arg.into()
+        }
+    });
+    println!("{:?}", diamond.source_names());
+
+    let mut buf = String::new();
+    loop {
+        match diamond.read_line_replacing(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => print!(
+                "{}: {}",
+                diamond.current_arg().unwrap().to_string_lossy(),
+                buf
+            ),
+            Err(e) => println!("[error] {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let n = env::var("MAX_LINES").map_or(2, |n| n.parse().unwrap());
+    let mut diamond = diamond_op::new().max_lines_per_file(n);
+    while let Some(line) = diamond.next_line()? {
+        print!(
+            "{}: {}",
+            diamond.current_arg().unwrap().to_string_lossy(),
+            line
+        );
+    }
+    for arg in diamond.truncated_sources() {
+        println!("truncated: {}", arg.to_string_lossy());
+    }
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    if let Some(n) = std::env::var_os("MAX_OPEN_FILES") {
+        diamond = diamond.max_open_files(n.to_str().unwrap().parse().unwrap());
+    }
+    for line in diamond.round_robin_iter() {
+        match line {
+            Ok(line) => println!("{:?}", line),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let numeric = env::var_os("NUMERIC").is_some();
+    let mut diamond = diamond_op::new();
+    if let Some(n) = env::var("MAX_LINES").ok().and_then(|n| n.parse().ok()) {
+        diamond = diamond.max_lines_per_file(n);
+    }
+    let lines = diamond.merge_sorted(|a, b| match numeric {
+        true => a.parse::<u64>().unwrap().cmp(&b.parse().unwrap()),
+        false => a.cmp(b),
+    });
+    for line in lines {
+        match line {
+            Ok(line) => print!("{}", line),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new().skip_read_errors();
+    let mut buf = String::new();
+    loop {
+        match diamond.read_line_replacing(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    let metrics = diamond.metrics();
+    println!("records: {}", metrics.records);
+    println!("bytes: {}", metrics.bytes);
+    println!("files_opened: {}", metrics.files_opened);
+    println!("errors: {}", metrics.errors);
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    while let Some(line) = diamond.next_line()? {
+        print!(
+            "{}: {}",
+            diamond.current_arg().unwrap().to_string_lossy(),
+            line
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    let mut records = Vec::new();
+    while let Some(record) = diamond.next_record(b'\n')? {
+        records.push(record);
+    }
+    for record in records {
+        println!("{:?}", String::from_utf8_lossy(&record));
+    }
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use std::{collections, io};
+
+fn main() -> io::Result<()> {
+    let mut seen = collections::HashSet::new();
+    for pair in diamond_op::new().normalize_for(|line| line.trim_end().to_lowercase()) {
+        let (key, line) = pair?;
+        let mark = if seen.insert(key.clone()) {
+            "new"
+        } else {
+            "dup"
+        };
+        print!("{} {:?} {}", mark, key, line);
+    }
+    Ok(())
+}
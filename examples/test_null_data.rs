@@ -0,0 +1,13 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let diamond = diamond_op::new().null_data();
+    let lines: Box<dyn Iterator<Item = io::Result<String>>> = match std::env::var_os("UNIQ") {
+        Some(_) => Box::new(diamond.uniq_iter()),
+        None => Box::new(diamond.line_iter()),
+    };
+    for line in lines {
+        println!("[{}]", line?.replace('\0', "\\0"));
+    }
+    Ok(())
+}
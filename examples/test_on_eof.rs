@@ -0,0 +1,20 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let fail = std::env::var_os("FAIL").is_some();
+    let mut diamond = diamond_op::new().on_eof(move || {
+        println!("on_eof fired");
+        match fail {
+            true => Err(io::Error::other("summary failed")),
+            false => Ok(()),
+        }
+    });
+    let mut buf = String::new();
+    for _ in 0..6 {
+        match diamond.read_line_replacing(&mut buf) {
+            Ok(n) => print!("{}: {}", n, if n == 0 { "EOF\n" } else { &buf }),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let diamond = diamond_op::new().on_file_handle(|arg, file| {
+        let len = file.metadata().expect("failed to read metadata").len();
+        println!("# {} ({} bytes)", arg.to_string_lossy(), len);
+    });
+    for line in diamond.line_iter() {
+        print!("{}", line?);
+    }
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    let mut buf = String::new();
+    while let Some(arg) = diamond.open_next()? {
+        println!("==> {} <==", arg.to_string_lossy());
+        diamond.read_line_replacing(&mut buf)?;
+        print!("{}", buf);
+    }
+    assert!(diamond.current_arg().is_none());
+    Ok(())
+}
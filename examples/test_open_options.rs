@@ -0,0 +1,22 @@
+use std::{fs, io};
+
+fn main() -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    #[cfg(target_os = "linux")]
+    std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, 0o400); // O_NOCTTY
+    if std::env::var_os("TEST_CREATE_NEW").is_some() {
+        // fails because `create_new` requires write access, showing the options are applied
+        options.create_new(true);
+    }
+    for (i, line) in diamond_op::new()
+        .open_options(options)
+        .line_iter()
+        .enumerate()
+    {
+        match line {
+            Ok(line) => print!("[{}] {}", i, line),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(())
+}
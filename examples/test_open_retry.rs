@@ -0,0 +1,15 @@
+use std::{io, time::Duration};
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new().open_retry(20, Duration::from_millis(100));
+    if std::env::var_os("RETRY_NOT_FOUND").is_some() {
+        diamond = diamond.open_retry_if(|e| e.kind() == io::ErrorKind::NotFound);
+    }
+    for line in diamond.line_iter() {
+        match line {
+            Ok(line) => print!("{}", line),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(())
+}
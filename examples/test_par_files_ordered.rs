@@ -0,0 +1,11 @@
+fn main() {
+    for item in diamond_op::new().par_files_ordered() {
+        match item {
+            Ok((arg, content)) => {
+                println!("== {} ({} bytes)", arg.to_string_lossy(), content.len());
+                print!("{}", String::from_utf8_lossy(&content));
+            }
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
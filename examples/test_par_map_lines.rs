@@ -0,0 +1,18 @@
+use std::{env, io, thread, time};
+
+fn main() -> io::Result<()> {
+    let workers = env::var("WORKERS").map_or(3, |n| n.parse().unwrap());
+    let lines = diamond_op::new().par_map_lines(workers, |line| {
+        // varies the latency by line to shuffle the completion order
+        thread::sleep(time::Duration::from_millis(line.len() as u64 % 7));
+        Ok(line)
+    });
+    let take = env::var("TAKE").map_or(usize::MAX, |n| n.parse().unwrap());
+    for line in lines.take(take) {
+        match line {
+            Ok(line) => print!("{}", line),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,10 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let whitespace = std::env::var_os("BLANK_IS_WHITESPACE").is_some();
+    let diamond = diamond_op::new().paragraph_blank_is_whitespace(whitespace);
+    for paragraph in diamond.paragraph_iter() {
+        print!("--\n{}", paragraph?);
+    }
+    Ok(())
+}
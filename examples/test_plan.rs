@@ -0,0 +1,23 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    if std::env::var_os("SKIP_SYMLINKS").is_some() {
+        diamond = diamond.symlinks(diamond_op::SymlinkPolicy::Skip);
+    }
+    for source in diamond.plan()? {
+        println!(
+            "plan: {} {:?} {}",
+            source.order,
+            source.resolved,
+            source.arg.to_string_lossy()
+        );
+    }
+    loop {
+        match diamond.open_next() {
+            Ok(Some(arg)) => println!("read: {}", arg.to_string_lossy()),
+            Ok(None) => return Ok(()),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    let mut buf = String::from("kept|");
+    loop {
+        match diamond.read_line(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => print!("{}", buf),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => println!("invalid: {:?}", buf),
+            Err(e) => return Err(e),
+        }
+        buf.truncate("kept|".len());
+    }
+    if std::env::var_os("COUNT").is_some() {
+        println!("bytes read: {}", diamond.bytes_read());
+        println!("offsets: {:?}", diamond.current_offsets());
+    }
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut i = 0;
+    let mut buf = String::new();
+    let mut diamond = diamond_op::new();
+    while diamond.read_line_replacing(&mut buf)? != 0 {
+        print!("[{}] {}", i, buf);
+        i += 1;
+    }
+    Ok(())
+}
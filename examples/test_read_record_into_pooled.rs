@@ -0,0 +1,19 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    let mut pool = Vec::new();
+    let mut allocated = 0;
+    loop {
+        if pool.is_empty() {
+            allocated += 1;
+        }
+        let Some(record) = diamond.read_record_into_pooled(b'\n', &mut pool)? else {
+            break;
+        };
+        println!("{:?}", String::from_utf8_lossy(&record));
+        pool.push(record);
+    }
+    println!("buffers allocated: {}, pooled: {}", allocated, pool.len());
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    let mut buf = Vec::new();
+    loop {
+        let (n, delim) = diamond.read_until_any(b";\n", &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        println!(
+            "{:?} {:?}",
+            String::from_utf8_lossy(&buf),
+            delim.map(char::from)
+        );
+        buf.clear();
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use std::io::{self, Read as _};
+
+fn main() -> io::Result<()> {
+    let mut reader = diamond_op::new().reader();
+    if std::env::var_os("TO_END").is_some() {
+        let mut buf = Vec::new();
+        match reader.read_to_end(&mut buf) {
+            Ok(n) => println!("{}", n),
+            Err(e) => println!("error after {} bytes: {}", buf.len(), e),
+        }
+        return Ok(());
+    }
+    let size = std::env::var("BUF").map_or(4096, |n| n.parse().unwrap());
+    let mut buf = vec![0; size];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(n) => {
+                println!("{}", n);
+                if n == 0 {
+                    return Ok(());
+                }
+            }
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use std::io::{self, BufRead as _};
+
+fn main() -> io::Result<()> {
+    let reader = diamond_op::new().reader_with_boundary_callback(|arg| {
+        println!("== {}", arg.to_string_lossy());
+    });
+    for line in reader.lines() {
+        println!("{}", line?);
+    }
+    Ok(())
+}
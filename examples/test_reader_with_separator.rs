@@ -0,0 +1,10 @@
+use std::{io, io::Read as _};
+
+fn main() -> io::Result<()> {
+    let mut buf = String::new();
+    diamond_op::new()
+        .reader_with_separator(b"<FF>\x0c".to_vec())
+        .read_to_string(&mut buf)?;
+    print!("{}", buf);
+    Ok(())
+}
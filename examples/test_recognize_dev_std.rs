@@ -0,0 +1,29 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    if env::var_os("RECOGNIZE").is_some() {
+        diamond = diamond.recognize_dev_std();
+    }
+    let mut diamond = diamond.buffer_stdin();
+    let mut prev = None;
+    let mut buf = String::new();
+    loop {
+        match diamond.read_line_replacing(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                println!("error: {}", e);
+                break;
+            }
+        }
+        if diamond.current_index() != prev {
+            prev = diamond.current_index();
+            let arg = diamond.current_arg().unwrap();
+            let is_stdin = diamond.is_stdin().unwrap();
+            println!("== {:?} (stdin: {})", arg, is_stdin);
+        }
+        print!("{}", buf);
+    }
+    Ok(())
+}
@@ -0,0 +1,9 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let starts_with_digit = |line: &str| line.starts_with(|c: char| c.is_ascii_digit());
+    for record in diamond_op::new().records_until(starts_with_digit) {
+        print!("--\n{}", record?);
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use std::{collections::HashMap, env, io};
+
+fn main() -> io::Result<()> {
+    let mut offsets = HashMap::new();
+    for pair in env::var("OFFSETS").unwrap_or_default().split(',') {
+        if let Some((arg, offset)) = pair.split_once('=') {
+            offsets.insert(arg.into(), offset.parse().unwrap());
+        }
+    }
+    let mut diamond = diamond_op::new().resume_from(&offsets);
+    if let Some(n) = env::var("LINES").ok().and_then(|n| n.parse().ok()) {
+        for _ in 0..n {
+            diamond.next_line()?;
+        }
+    } else {
+        while let Some(line) = diamond.next_line()? {
+            print!("{}", line);
+        }
+    }
+    for warning in diamond.take_errors() {
+        println!("warning: {}", warning);
+    }
+    let mut offsets = diamond.current_offsets().into_iter().collect::<Vec<_>>();
+    offsets.sort();
+    for (arg, offset) in offsets {
+        println!("{}={}", arg.to_string_lossy(), offset);
+    }
+    Ok(())
+}
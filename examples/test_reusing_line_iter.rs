@@ -0,0 +1,17 @@
+use std::{cell::RefCell, io};
+
+fn main() -> io::Result<()> {
+    let slot = RefCell::new(String::new());
+    let mut capacity = 0;
+    let mut lines = 0;
+    for ret in diamond_op::new().reusing_line_iter(&slot) {
+        ret?;
+        let line = slot.borrow();
+        print!("{}", line);
+        capacity = capacity.max(line.capacity());
+        lines += 1;
+    }
+    println!("lines: {}", lines);
+    println!("reused: {}", slot.borrow().capacity() == capacity);
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use std::io::{self, BufRead};
+
+fn main() -> io::Result<()> {
+    let mut diamond = match std::env::var_os("NAMED") {
+        Some(_) => {
+            let sources: [(&str, &[u8]); 3] =
+                [("A", b"a1\na2\n"), ("B", b"\xff\n"), ("C", b"c1\n")];
+            diamond_op::from_named_readers(sources.map(|(name, content)| {
+                let reader: Box<dyn BufRead + Send> = Box::new(content);
+                (name.into(), reader)
+            }))
+        }
+        None => diamond_op::new(),
+    };
+    if std::env::var_os("HEADER").is_some() {
+        diamond = diamond.first_source_callback(|reader| {
+            let mut header = String::new();
+            reader.read_line(&mut header)?;
+            println!("header: {:?}", header);
+            Ok(())
+        });
+    }
+    for line in diamond.reverse_line_iter()? {
+        match line {
+            Ok(line) => print!("{}", line),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    if let Some(n) = env::var("MAX_LINES").ok().and_then(|n| n.parse().ok()) {
+        diamond = diamond.max_lines_per_file(n);
+    }
+    for line in diamond.round_robin_iter() {
+        println!("{:?}", line?);
+    }
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let numbers = env::var("NUMBERS").unwrap_or_default();
+    let numbers: Vec<u64> = numbers.split(',').filter_map(|n| n.parse().ok()).collect();
+    for line in diamond_op::new().select_lines(&numbers) {
+        match line {
+            Ok(line) => print!("{}", line),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(())
+}
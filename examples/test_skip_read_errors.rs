@@ -0,0 +1,13 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new().skip_read_errors();
+    let mut buf = String::new();
+    while diamond.read_line_replacing(&mut buf)? != 0 {
+        print!("{}", buf);
+    }
+    for e in diamond.take_errors() {
+        println!("[skipped] {}", e);
+    }
+    Ok(())
+}
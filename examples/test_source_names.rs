@@ -0,0 +1,15 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    println!("{:?}", diamond.source_names());
+
+    let mut buf = String::new();
+    while diamond.read_line(&mut buf)? != 0 {
+        print!("{:?} {}", diamond.source_names(), buf);
+        buf.clear();
+    }
+
+    println!("{:?}", diamond.source_names());
+    Ok(())
+}
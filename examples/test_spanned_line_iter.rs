@@ -0,0 +1,20 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    if std::env::var_os("SKIP").is_some() {
+        diamond = diamond.skip_while_line(|line| line.starts_with('#'));
+    }
+    for line in diamond.spanned_line_iter() {
+        let (span, line) = line?;
+        println!(
+            "{}:{} [{}+{}] {:?}",
+            span.source.to_string_lossy(),
+            span.line_number,
+            span.start_byte,
+            span.len,
+            line
+        );
+    }
+    Ok(())
+}
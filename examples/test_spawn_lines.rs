@@ -0,0 +1,15 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let (rx, handle) = diamond_op::new().spawn_lines();
+    for (i, line) in rx.iter().enumerate() {
+        print!("[{}] {}", i, line?);
+    }
+    handle.join().expect("reader thread panicked");
+
+    // the thread also exits when the receiver is dropped before the EOF
+    let (rx, handle) = diamond_op::new().spawn_lines();
+    drop(rx);
+    handle.join().expect("reader thread panicked");
+    Ok(())
+}
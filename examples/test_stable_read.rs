@@ -0,0 +1,32 @@
+use std::{env, fs, io, io::Write as _};
+
+fn main() -> io::Result<()> {
+    let path = env::args().nth(1).unwrap();
+    let mode = env::var("MODE").unwrap_or_default();
+    fs::write(&path, "first line\nsecond line\n")?;
+    let mut diamond = diamond_op::DiamondConfig::default().build_with_args([&path, "a.txt"]);
+    if env::var_os("STABLE").is_some() {
+        diamond = diamond.stable_read();
+    }
+    print!("{}", diamond.next_line()?.unwrap());
+    match mode.as_str() {
+        "append" => writeln!(
+            fs::OpenOptions::new().append(true).open(&path)?,
+            "appended line"
+        )?,
+        "truncate" => fs::File::create(&path)?.set_len(0)?,
+        _ => {}
+    }
+    let mut buf = String::new();
+    loop {
+        match diamond.read_line_replacing(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => print!("{}", buf),
+            Err(e) => {
+                println!("error: {}", e.to_string().replace(&path, "FILE"));
+                break;
+            }
+        }
+    }
+    fs::remove_file(&path)
+}
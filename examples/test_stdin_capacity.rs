@@ -0,0 +1,18 @@
+use std::{io, io::BufRead as _};
+
+fn main() -> io::Result<()> {
+    let mut max_len = 0;
+    let mut reader = diamond_op::new().stdin_capacity(16).reader();
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        let len = buf.len();
+        print!("{}", String::from_utf8_lossy(buf));
+        max_len = max_len.max(len);
+        reader.consume(len);
+    }
+    println!("[max buffer length] {}", max_len);
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use std::io::{self, BufRead as _};
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    if std::env::var_os("TIMEOUT").is_some() {
+        diamond = diamond.stdin_timeout(std::time::Duration::from_secs(5));
+    }
+    let mut buf = String::new();
+    while diamond.read_line_replacing(&mut buf)? != 0 {
+        print!("{}", buf);
+        if diamond.is_stdin() == Some(false) {
+            // blocks forever if the diamond still held the lock on standard input
+            let mut rest = String::new();
+            let n = io::stdin().lock().read_line(&mut rest)?;
+            println!("direct read after stdin: {} bytes", n);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use std::time::Duration;
+
+fn main() {
+    let diamond = diamond_op::new().stdin_timeout(Duration::from_millis(500));
+    for line in diamond.line_iter() {
+        match line {
+            Ok(line) => print!("{}", line),
+            Err(e) => {
+                println!("[error] {:?}: {}", e.kind(), e);
+                break;
+            }
+        }
+    }
+}
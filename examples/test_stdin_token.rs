@@ -0,0 +1,18 @@
+fn main() {
+    let mut diamond = diamond_op::new();
+    if let Some(token) = std::env::var_os("TEST_STDIN_TOKEN") {
+        diamond = diamond.stdin_token(token);
+    }
+    let mut buf = String::new();
+    loop {
+        match diamond.read_line_replacing(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => print!(
+                "{}: {}",
+                diamond.current_arg().unwrap().to_string_lossy(),
+                buf
+            ),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    for (i, line) in diamond_op::new().stream_line_iter().enumerate() {
+        print!("[{}] {}", i, line?);
+    }
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    if env::var_os("COMPAT").is_some() {
+        diamond = diamond.strict_bufread_compat();
+    }
+    if let Ok(stop) = env::var("TAKE_UNTIL") {
+        diamond = diamond.take_while_line(move |line| line != stop);
+    }
+    if let Ok(prefix) = env::var("SKIP_PREFIX") {
+        diamond = diamond.skip_while_line(move |line| line.starts_with(&prefix));
+    }
+    let mut buf = String::new();
+    loop {
+        buf.clear();
+        let n = diamond.read_line(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        println!("{:2}: {:?}", n, buf);
+    }
+}
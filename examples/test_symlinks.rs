@@ -0,0 +1,20 @@
+use diamond_op::SymlinkPolicy;
+use std::env;
+
+fn main() {
+    let policy = match env::var("SYMLINK_POLICY").as_deref() {
+        Ok("skip") => SymlinkPolicy::Skip,
+        Ok("error") => SymlinkPolicy::Error,
+        _ => SymlinkPolicy::Follow,
+    };
+
+    for line in diamond_op::new().symlinks(policy).line_iter() {
+        match line {
+            Ok(line) => print!("{}", line),
+            Err(e) => {
+                println!("[error] {}", e);
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    if env::var_os("SKIP").is_some() {
+        diamond = diamond.skip_while_line(|line| line.starts_with('#'));
+    }
+    if env::var_os("TAKE").is_some() {
+        diamond = diamond.take_while_line(|line| line != "---");
+    }
+    if env::var_os("READ_LINE").is_some() {
+        let mut buf = String::new();
+        while diamond.read_line_replacing(&mut buf)? != 0 {
+            print!(
+                "{}: {}",
+                diamond.current_arg().unwrap().to_string_lossy(),
+                buf
+            );
+        }
+    } else {
+        while let Some(line) = diamond.next_line()? {
+            print!(
+                "{}: {}",
+                diamond.current_arg().unwrap().to_string_lossy(),
+                line
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,8 @@
+use std::io::{self, BufRead as _, Write as _};
+
+fn main() -> io::Result<()> {
+    let mut sink = Vec::new();
+    let lines = diamond_op::new().tee(&mut sink).lines().count();
+    eprintln!("{} lines", lines);
+    io::stdout().write_all(&sink)
+}
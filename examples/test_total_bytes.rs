@@ -0,0 +1,19 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let mut diamond = diamond_op::new();
+    println!("{:?} / {:?}", diamond.bytes_read(), diamond.total_bytes()?);
+
+    let mut buf = String::new();
+    while diamond.read_line_replacing(&mut buf)? != 0 {
+        print!(
+            "{:?} / {:?} {}",
+            diamond.bytes_read(),
+            diamond.total_bytes()?,
+            buf
+        );
+    }
+
+    println!("{:?} / {:?}", diamond.bytes_read(), diamond.total_bytes()?);
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use diamond_op::LineError;
+
+fn main() {
+    for line in diamond_op::new().try_line_iter() {
+        match line {
+            Ok(line) => print!("{}", line),
+            Err(LineError::InvalidUtf8 { arg, line, bytes }) => {
+                println!("invalid: {}:{}: {:?}", arg.to_string_lossy(), line, bytes);
+            }
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
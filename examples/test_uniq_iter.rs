@@ -0,0 +1,8 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    for line in diamond_op::new().uniq_iter() {
+        print!("{}", line?);
+    }
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let opts = diamond_op::UniqOptions {
+        ignore_case: env::var_os("IGNORE_CASE").is_some(),
+        ignore_whitespace: env::var_os("IGNORE_WHITESPACE").is_some(),
+        count: env::var_os("COUNT").is_some(),
+    };
+    for line in diamond_op::new().uniq_iter_with(opts) {
+        print!("{}", line?);
+    }
+    Ok(())
+}
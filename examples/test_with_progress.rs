@@ -0,0 +1,19 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let interval = std::env::var("INTERVAL").map_or(0, |n| n.parse().unwrap());
+    let diamond = diamond_op::new().with_progress(|p| {
+        println!(
+            "{}/{:?} {} {}/{:?}",
+            p.bytes_read,
+            p.total_bytes,
+            p.current_source.to_string_lossy(),
+            p.source_index,
+            p.total_sources
+        );
+    });
+    for line in diamond.progress_interval(interval).line_iter() {
+        line?;
+    }
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use std::io;
+
+fn main() -> io::Result<()> {
+    let words: Vec<_> = if std::env::var_os("COMMA").is_some() {
+        diamond_op::new()
+            .word_iter_by(|b| b == b',')
+            .collect::<io::Result<_>>()?
+    } else {
+        diamond_op::new().word_iter().collect::<io::Result<_>>()?
+    };
+    for word in words {
+        println!("{:?}", word);
+    }
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use std::{env, io};
+
+fn main() -> io::Result<()> {
+    let diamond = diamond_op::new().yield_empty_sources();
+    let lines: Box<dyn Iterator<Item = io::Result<String>>> = match env::var("ITER").as_deref() {
+        Ok("uniq") => Box::new(diamond.uniq_iter()),
+        Ok("dedup") => Box::new(diamond.dedup_global()),
+        Ok("dedup_by_hash") => Box::new(diamond.dedup_global_by_hash()),
+        Ok("paragraph") => Box::new(diamond.paragraph_iter()),
+        Ok("strip_comments") => Box::new(diamond.strip_comments('#')),
+        _ => Box::new(diamond.line_iter()),
+    };
+    for line in lines {
+        println!("{:?}", line?);
+    }
+    Ok(())
+}
@@ -14,9 +14,20 @@
 //! # Prints all lines from file1.txt, file2.txt, standard input, and file3.txt.
 //! mycmd file1.txt file2.txt - file3.txt
 //! ```
+//!
+//! # Crate features
+//!
+//! The core functionality has no dependencies other than the standard library. Integrations with
+//! other crates are provided behind named Cargo features, none of which is enabled by default.
+
+#![cfg_attr(docsrs, feature(doc_cfg))]
+#![allow(clippy::needless_doctest_main)]
 
 use std::io::{self, BufRead};
-use std::{env, ffi, fs, iter, slice};
+use std::{
+    cell, cmp, collections, env, error, ffi, fmt, fs, hash, iter, mem, panic, path, rc, slice, str,
+    sync, sync::mpsc, thread, time,
+};
 
 /// Returns a diamond operator instance.
 ///
@@ -25,14 +36,120 @@ pub fn new() -> Diamond {
     Diamond::default()
 }
 
+/// Returns a diamond operator instance that reads the files listed in the file at `path` instead of
+/// command line arguments.
+///
+/// The list file contains one path per line, and a path of "-" refers to standard input as usual.
+/// Leading and trailing whitespace is trimmed from each line, and blank lines and lines starting
+/// with `#` are ignored. Unlike command line arguments, an empty list does not fall back to
+/// standard input.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// for line in diamond_op::from_list_file("inputs.txt")?.line_iter() {
+///     print!("{}", line?);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn from_list_file(path: impl AsRef<path::Path>) -> io::Result<Diamond> {
+    let list = fs::read_to_string(path)?;
+    let args = list
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(ffi::OsString::from)
+        .collect();
+    Ok(Diamond {
+        args: Args(Some(args)),
+        ..Default::default()
+    })
+}
+
+/// Returns a diamond operator instance that reads the given readers instead of files and standard
+/// input.
+///
+/// Each reader is read as a source named by the paired argument, which is reported by
+/// [`Diamond::current_arg`] and the errors as if it were a command line argument. This is useful
+/// to test the code that consumes a [`Diamond`] with in-memory content, e.g. from property-based
+/// tests, without touching the file system. The readers cannot be rewound, and
+/// [`Diamond::total_bytes`] returns `None` for them.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::{BufRead, Cursor};
+/// let sources: Vec<(std::ffi::OsString, Box<dyn BufRead + Send>)> = vec![
+///     ("a".into(), Box::new(Cursor::new("one\ntwo\n"))),
+///     ("b".into(), Box::new(Cursor::new("three\n"))),
+/// ];
+/// let lines = diamond_op::from_named_readers(sources).line_iter();
+/// assert_eq!(lines.collect::<Result<Vec<_>, _>>()?, ["one\n", "two\n", "three\n"]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn from_named_readers<I>(sources: I) -> Diamond
+where
+    I: IntoIterator<Item = (ffi::OsString, Box<dyn BufRead + Send>)>,
+{
+    let (args, readers) = sources.into_iter().unzip();
+    Diamond {
+        args: Args(Some(args)),
+        named_readers: Some(readers),
+        ..Default::default()
+    }
+}
+
 /// A structure that reads lines, like Perl's diamond (`<>`) operator and many Unix filter programs,
 /// from files and standard input ("-") specified by command line arguments or from standard input
 /// if no argument is given.
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct Diamond {
     cur_file: Option<Reader>,
     cur_arg: Option<ffi::OsString>,
+    cur_records: u64,
+    cur_bytes: u64,
+    /// The number of records, or chunks of bytes, yielded from the current source, which excludes
+    /// those filtered out by the line predicates to tell whether the source is empty.
+    cur_yielded: u64,
+    /// Whether the lines of the current source are still being skipped by `skip_while_line`.
+    cur_skipping: bool,
+    bytes_read: u64,
+    args_taken: usize,
     args: Args,
+    errors: Vec<io::Error>,
+    stdin_content: Option<sync::Arc<[u8]>>,
+    empty_sources: Vec<ffi::OsString>,
+    truncated_sources: Vec<ffi::OsString>,
+    progress_reported: u64,
+    /// The `total_bytes` and `total_sources` of `Progress` for the current source, computed once
+    /// per source as they involve the metadata of all the remaining sources.
+    progress_totals: Option<(Option<u64>, usize)>,
+    named_readers: Option<collections::VecDeque<BoxedReader>>,
+    metrics: Metrics,
+    resume_offsets: collections::HashMap<ffi::OsString, u64>,
+    /// The offsets consumed from the sources read before the current one.
+    offsets: collections::HashMap<ffi::OsString, u64>,
+    /// The argument of the current source and the offset at which reading it started.
+    cur_offset: Option<(ffi::OsString, u64)>,
+    chained: Option<Box<Diamond>>,
+    /// The standard input reader of an abandoned source, kept for the next source reading standard
+    /// input so that the bytes it has read ahead are not lost.
+    spare_stdin: Option<Reader>,
+    config: DiamondConfig,
+    hooks: Hooks,
+}
+
+/// Shows the progress of reading rather than the internal state.
+impl fmt::Debug for Diamond {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let remaining = self.remaining_sources();
+        f.debug_struct("Diamond")
+            .field("current_arg", &self.cur_arg)
+            .field("current_line", &self.cur_records)
+            .field("bytes_read", &self.bytes_read)
+            .field("remaining_sources", &remaining)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Diamond {
@@ -53,13 +170,74 @@ impl Diamond {
     /// # Ok::<(), std::io::Error>(())
     /// ```
     pub fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
-        self.read_inner(|reader| reader.read_until(byte, buf))
+        let mut total = 0;
+        loop {
+            let n = self.read_inner(|reader| {
+                let len = buf.len();
+                reader
+                    .read_until(byte, buf)
+                    .inspect_err(|_| buf.truncate(len))
+            })?;
+            total += n;
+            if n == 0 || !self.config.strict_bufread_compat || buf.last() == Some(&byte) {
+                return Ok(total);
+            }
+        }
+    }
+
+    /// Reads all bytes into `buf` until any of the delimiter bytes in `delims` or EOF is reached,
+    /// returning the number of bytes read and the delimiter found.
+    ///
+    /// This function works in the same way as [`read_until`](Self::read_until), except that it
+    /// takes a set of delimiters and also returns the one that terminated the record, or `None` if
+    /// the record was terminated by the EOF of a file or standard input. An empty `delims` reads
+    /// each file or standard input to EOF.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut buf = Vec::new();
+    /// let mut diamond = diamond_op::new();
+    /// loop {
+    ///     match diamond.read_until_any(b";\n", &mut buf)? {
+    ///         (0, _) => break,
+    ///         (_, Some(b';')) => print!("{} ", String::from_utf8_lossy(&buf)),
+    ///         (_, _) => print!("{}", String::from_utf8_lossy(&buf)),
+    ///     }
+    ///     buf.clear();
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn read_until_any(
+        &mut self,
+        delims: &[u8],
+        buf: &mut Vec<u8>,
+    ) -> io::Result<(usize, Option<u8>)> {
+        let mut table = [false; 256];
+        for &b in delims {
+            table[usize::from(b)] = true;
+        }
+        let mut found = None;
+        let n = self.read_inner(|reader| {
+            let len = buf.len();
+            let (n, delim) =
+                read_until_any(reader, &table, buf).inspect_err(|_| buf.truncate(len))?;
+            found = delim;
+            Ok(n)
+        })?;
+        Ok((n, found))
     }
 
     /// Reads all bytes into `buf` until a newline (the `0xA` byte) or EOF is reached.
     ///
     /// This function works in the same way as [`BufRead::read_line`], except that it also returns
-    /// at the EOF of each file or standard input that does not end with a newline byte.
+    /// at the EOF of each file or standard input that does not end with a newline byte. The line
+    /// ends at the byte set by [`line_terminator`](Self::line_terminator) instead if set.
+    ///
+    /// Unlike `BufRead::read_line`, if the line is not valid UTF-8, this function fails with
+    /// [`io::ErrorKind::InvalidData`], leaving `buf` unchanged, after consuming the whole line, so
+    /// that the next call reads the next line. The line still counts toward the line numbers and
+    /// [`bytes_read`](Self::bytes_read). `buf` is left unchanged on any other error, too.
     ///
     /// # Examples
     ///
@@ -73,7 +251,159 @@ impl Diamond {
     /// # Ok::<(), std::io::Error>(())
     /// ```
     pub fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
-        self.read_inner(|reader| reader.read_line(buf))
+        let term = self.config.line_terminator;
+        let start = buf.len();
+        let mut total = 0;
+        loop {
+            let n = self.read_inner(|reader| read_line(reader, term, buf))?;
+            total += n;
+            if n != 0 && self.config.strict_bufread_compat && !buf.ends_with(char::from(term)) {
+                continue; // joins the rest of the line from the following sources
+            }
+            // filters the complete line once it is joined across sources
+            if total != 0 && !self.filter_line(&buf[start..]) {
+                buf.truncate(start);
+                total = 0;
+                continue;
+            }
+            return Ok(total);
+        }
+    }
+
+    /// Clears `buf` and then reads a line into it in the same way as [`read_line`](Self::read_line).
+    ///
+    /// Unlike `read_line`, which appends to `buf` like [`BufRead::read_line`], this function leaves
+    /// only the latest line in `buf`, so the caller does not need to clear it in each iteration.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut buf = String::new();
+    /// let mut diamond = diamond_op::new();
+    /// while diamond.read_line_replacing(&mut buf)? != 0 {
+    ///     print!("{}", buf);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn read_line_replacing(&mut self, buf: &mut String) -> io::Result<usize> {
+        buf.clear();
+        self.read_line(buf)
+    }
+
+    /// Reads a line into a new `String`, returning `None` at the EOF of all files and standard
+    /// input.
+    ///
+    /// The line is read in the same way as [`read_line`](Self::read_line) and includes the newline
+    /// byte if any. Unlike [`line_iter`](Self::line_iter), this function does not consume `self`,
+    /// so [`current_arg`](Self::current_arg) and the like remain available in the loop.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new();
+    /// while let Some(line) = diamond.next_line()? {
+    ///     print!("{}: {}", diamond.current_arg().unwrap().to_string_lossy(), line);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn next_line(&mut self) -> io::Result<Option<String>> {
+        let term = self.config.line_terminator;
+        let mut buf = String::new();
+        loop {
+            match self.read_record(|reader| read_line(reader, term, &mut buf))? {
+                Some(n) if n != 0 && !self.filter_line(&buf) => buf.clear(),
+                ret => return Ok(ret.map(|_| buf)),
+            }
+        }
+    }
+
+    /// Folds all the lines into an accumulator by applying `f` to the accumulator, the argument
+    /// name of the current source, and each line, like [`Iterator::try_fold`].
+    ///
+    /// The lines are read in the same way as [`read_line_replacing`](Self::read_line_replacing)
+    /// into a buffer reused across lines, and each includes the newline byte if any. The name is
+    /// the one reported by [`current_arg`](Self::current_arg). An error returned by `f` or
+    /// encountered while reading stops the fold and is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// // Counts the lines of each file.
+    /// let counts = diamond_op::new().fold_lines(HashMap::new(), |mut counts, arg, _| {
+    ///     *counts.entry(arg.to_owned()).or_insert(0) += 1;
+    ///     Ok(counts)
+    /// })?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn fold_lines<B, F>(&mut self, init: B, mut f: F) -> io::Result<B>
+    where
+        F: FnMut(B, &ffi::OsStr, &str) -> io::Result<B>,
+    {
+        let mut acc = init;
+        let mut buf = String::new();
+        while self.read_line_replacing(&mut buf)? != 0 {
+            acc = f(acc, self.current_arg().unwrap_or_default(), &buf)?;
+        }
+        Ok(acc)
+    }
+
+    /// Reads a record terminated by the delimiter `sep` or EOF into a new buffer, returning `None`
+    /// at the EOF of all files and standard input.
+    ///
+    /// The record is read in the same way as [`read_until`](Self::read_until), but the delimiter
+    /// is stripped from the returned buffer. This is useful in a manual loop that handles errors
+    /// with `?`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Reads NUL-separated records, e.g. from `find -print0`.
+    /// let mut diamond = diamond_op::new();
+    /// while let Some(record) = diamond.next_record(b'\0')? {
+    ///     println!("{}", String::from_utf8_lossy(&record));
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn next_record(&mut self, sep: u8) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = Vec::new();
+        Ok(self.next_record_in(sep, &mut buf)?.then_some(buf))
+    }
+
+    /// Reads a record terminated by the delimiter `sep` or EOF into a buffer taken from `pool`,
+    /// returning `None` at the EOF of all files and standard input.
+    ///
+    /// This function works in the same way as [`next_record`](Self::next_record), except that it
+    /// reuses a buffer popped from `pool`, if any, instead of allocating a new one. Pushing the
+    /// returned buffers back to `pool` once processed saves an allocation per record in
+    /// high-throughput loops. The buffer is returned to `pool` if no record is read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new();
+    /// let mut pool = Vec::new();
+    /// while let Some(record) = diamond.read_record_into_pooled(b'\n', &mut pool)? {
+    ///     println!("{}", record.len());
+    ///     pool.push(record);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn read_record_into_pooled(
+        &mut self,
+        sep: u8,
+        pool: &mut Vec<Vec<u8>>,
+    ) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = pool.pop().unwrap_or_default();
+        buf.clear();
+        match self.next_record_in(sep, &mut buf) {
+            Ok(true) => Ok(Some(buf)),
+            ret => {
+                pool.push(buf);
+                ret.map(|_| None)
+            }
+        }
     }
 
     /// Returns an iterator over the lines of all files and standard input.
@@ -95,147 +425,4558 @@ impl Diamond {
     /// # Ok::<(), std::io::Error>(())
     /// ```
     pub fn line_iter(mut self) -> impl Iterator<Item = io::Result<String>> {
+        iter::from_fn(move || self.next_line().transpose())
+    }
+
+    /// Returns an iterator that reads each line of all files and standard input into `slot`,
+    /// yielding `()` in place of the line.
+    ///
+    /// Each iteration reads the next line in the same way as
+    /// [`read_line_replacing`](Self::read_line_replacing), replacing the content of `slot`, so the
+    /// lines can be processed with iterator adapters without allocating a `String` per line. The
+    /// caller reads the line from `slot` between iterations, which is a [`cell::RefCell`] because
+    /// the iterator keeps a reference to it. Advancing the iterator while `slot` is borrowed
+    /// panics.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    ///
+    /// let slot = RefCell::new(String::new());
+    /// for ret in diamond_op::new().reusing_line_iter(&slot) {
+    ///     ret?;
+    ///     print!("{}", slot.borrow());
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn reusing_line_iter(
+        mut self,
+        slot: &cell::RefCell<String>,
+    ) -> impl Iterator<Item = io::Result<()>> {
         iter::from_fn(move || {
-            let mut buf = String::new();
-            match self.read_line(&mut buf) {
-                Ok(0) => None,
-                Ok(_) => Some(Ok(buf)),
-                Err(e) => Some(Err(e)),
-            }
+            let ret = self.read_line_replacing(&mut slot.borrow_mut());
+            ret.map(|n| (n != 0).then_some(())).transpose()
         })
     }
 
-    /// Returns a reader that reads bytes as a single stream.
+    /// Returns an iterator over batches of up to `n` lines of all files and standard input.
     ///
-    /// The returned reader reads bytes, treating all files and standard input as a consolidated
-    /// single stream and ignoring the EOF of each file or standard input in between, which is
-    /// different from the behavior of other methods in this type.
+    /// By default, a batch does not span files, so the last batch of each file or standard input
+    /// may hold fewer than `n` lines. Set
+    /// [`line_batches_cross_files`](Self::line_batches_cross_files) to fill batches across file
+    /// boundaries instead, in which case only the final batch may be short. The iterator yields an
+    /// error of [`io::ErrorKind::InvalidInput`] if `n` is zero.
     ///
     /// # Examples
     ///
-    /// Note that the example below uses [`BufRead::lines`] and thus works differently from
-    /// [`line_iter`](Self::line_iter).
-    ///
     /// ```rust
-    /// use std::io::BufRead as _;
-    /// for line in diamond_op::new().reader().lines() {
-    ///     println!("{}", line?);
+    /// for batch in diamond_op::new().line_batches(1000) {
+    ///     let batch = batch?;
+    ///     println!("inserting {} rows", batch.len());
     /// }
     /// # Ok::<(), std::io::Error>(())
     /// ```
-    pub fn reader(self) -> impl BufRead {
-        struct SingleStreamReader(Diamond);
-
-        impl io::Read for SingleStreamReader {
-            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-                let n = self.fill_buf()?.read(buf)?;
-                self.consume(n);
-                Ok(n)
+    pub fn line_batches(mut self, n: usize) -> impl Iterator<Item = io::Result<Vec<String>>> {
+        let cross_files = self.config.line_batches_cross_files;
+        let mut batch = Vec::new();
+        let mut batch_source = 0; // `args_taken` of the source of `batch`
+        let mut done = false;
+        iter::from_fn(move || {
+            if done {
+                return None;
+            } else if n == 0 {
+                done = true;
+                return Some(Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "batch size must be positive",
+                )));
             }
-        }
-
-        impl BufRead for SingleStreamReader {
-            fn fill_buf(&mut self) -> io::Result<&[u8]> {
-                loop {
-                    if let Some(reader) = &mut self.0.cur_file {
-                        let ret = reader.as_buf_read_mut().fill_buf()?;
-                        if !ret.is_empty() {
-                            // Intends to `return Ok(ret);` but hacks the borrow checker to work
-                            // around the "conditional returns" limitation:
-                            // https://github.com/rust-lang/rust/issues/51545
-                            return Ok(unsafe { slice::from_raw_parts(ret.as_ptr(), ret.len()) });
+            while batch.len() < n {
+                match self.next_line() {
+                    Ok(Some(line)) if line.is_empty() => {} // skips the empty source marker
+                    Ok(Some(line)) => {
+                        let source = self.args_taken;
+                        let ended = !cross_files && !batch.is_empty() && source != batch_source;
+                        let ended = ended.then(|| mem::take(&mut batch));
+                        batch_source = source;
+                        batch.push(line);
+                        if ended.is_some() {
+                            return ended.map(Ok);
                         }
                     }
-                    if !self.0.prepare_next()? {
-                        return Ok(&[]);
+                    Ok(None) => {
+                        done = true;
+                        return (!batch.is_empty()).then(|| Ok(mem::take(&mut batch)));
                     }
+                    Err(e) => return Some(Err(e)),
                 }
             }
+            Some(Ok(mem::take(&mut batch)))
+        })
+    }
+
+    /// Returns an iterator over the lines of all files and standard input, each paired with its
+    /// location.
+    ///
+    /// The [`RecordSpan`] of each line holds the source, the line number within it, and the range
+    /// of bytes the line occupies in the whole input, which is useful to report diagnostics and to
+    /// seek back to the line later.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().spanned_line_iter() {
+    ///     let (span, line) = line?;
+    ///     if line.contains('\t') {
+    ///         let source = span.source.to_string_lossy();
+    ///         eprintln!("{}:{}: tab found at byte {}", source, span.line_number, span.start_byte);
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn spanned_line_iter(mut self) -> impl Iterator<Item = io::Result<(RecordSpan, String)>> {
+        iter::from_fn(move || {
+            let line = self.next_line().transpose()?;
+            Some(line.map(|line| {
+                // measured after the read as lines may have been skipped by `skip_while_line`
+                let start_byte = self.bytes_read - line.len() as u64;
+                let span = RecordSpan {
+                    source: self.cur_arg.clone().unwrap_or_default(),
+                    line_number: self.cur_records,
+                    start_byte,
+                    len: line.len(),
+                };
+                (span, line)
+            }))
+        })
+    }
 
-            fn consume(&mut self, amount: usize) {
-                if let Some(reader) = &mut self.0.cur_file {
-                    reader.as_buf_read_mut().consume(amount);
+    /// Returns an iterator over the lines of all files and standard input that continues past lines
+    /// that are not valid UTF-8.
+    ///
+    /// The returned iterator works like [`line_iter`](Self::line_iter), except that a line that
+    /// cannot be decoded as UTF-8 is yielded as [`LineError::InvalidUtf8`] carrying its raw bytes,
+    /// and the iteration resumes at the next line. Other errors are yielded as [`LineError::Io`]
+    /// and end the iteration.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use diamond_op::LineError;
+    /// for line in diamond_op::new().try_line_iter() {
+    ///     match line {
+    ///         Ok(line) => print!("{}", line),
+    ///         Err(e @ LineError::InvalidUtf8 { .. }) => eprintln!("skipped: {}", e),
+    ///         Err(e) => return Err(e.into()),
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn try_line_iter(mut self) -> impl Iterator<Item = Result<String, LineError>> {
+        let term = self.config.line_terminator;
+        let mut done = false;
+        iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let mut buf = Vec::new();
+            match self.read_record(|reader| {
+                let len = buf.len();
+                reader
+                    .read_until(term, &mut buf)
+                    .inspect_err(|_| buf.truncate(len))
+            }) {
+                Ok(Some(_)) => Some(String::from_utf8(buf).map_err(|e| LineError::InvalidUtf8 {
+                    arg: self.cur_arg.clone().unwrap_or_default(),
+                    line: self.cur_records,
+                    bytes: e.into_bytes(),
+                })),
+                Ok(None) => None,
+                Err(e) => {
+                    done = true;
+                    Some(Err(LineError::Io(e)))
                 }
             }
-        }
+        })
+    }
 
-        SingleStreamReader(self)
+    /// Spawns a thread that reads the lines of all files and standard input and sends them over a
+    /// bounded channel.
+    ///
+    /// The lines are the same as those yielded by [`line_iter`](Self::line_iter). The thread
+    /// blocks when the channel is full, so lines are read no faster than the receiver takes them.
+    /// It exits at the EOF of all sources or when the receiver is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let (rx, handle) = diamond_op::new().spawn_lines();
+    /// for line in rx {
+    ///     print!("{}", line?);
+    /// }
+    /// handle.join().unwrap();
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn spawn_lines(mut self) -> (mpsc::Receiver<io::Result<String>>, thread::JoinHandle<()>) {
+        let (tx, rx) = mpsc::sync_channel(64);
+        let handle = thread::spawn(move || {
+            while let Some(line) = self.next_line().transpose() {
+                if tx.send(line).is_err() {
+                    break; // the receiver has been dropped
+                }
+            }
+        });
+        (rx, handle)
     }
 
-    /// Returns the command line argument currently being processed or `None` before the first line
-    /// has been read or after all the files have been read.
-    pub fn current_arg(&self) -> Option<&ffi::OsStr> {
-        self.cur_arg.as_deref()
+    /// Returns an iterator over the lines of all files and standard input, paired with a running
+    /// 0-based index.
+    ///
+    /// The index counts only the lines actually yielded, so it stays contiguous across files and
+    /// is not advanced by errors, unlike `line_iter().enumerate()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().indexed_line_iter() {
+    ///     let (i, line) = line?;
+    ///     print!("[{}] {}", i, line);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn indexed_line_iter(self) -> impl Iterator<Item = io::Result<(usize, String)>> {
+        let mut index = 0;
+        self.line_iter().map(move |line| {
+            let line = line?;
+            index += 1;
+            Ok((index - 1, line))
+        })
     }
 
-    fn read_inner(
-        &mut self,
-        mut f: impl FnMut(&mut dyn BufRead) -> io::Result<usize>,
-    ) -> io::Result<usize> {
-        loop {
-            if let Some(reader) = &mut self.cur_file {
-                let ret = f(reader.as_buf_read_mut())?;
-                if ret != 0 {
-                    return Ok(ret);
-                }
+    /// Returns an iterator over the lines of each file or standard input at the given line numbers,
+    /// like `sed -s -n '1p;5p'`.
+    ///
+    /// `numbers` are 1-based line numbers within each file or standard input, not across the whole
+    /// input, and may be given in any order and with duplicates. The rest of a file is skipped
+    /// without being read once its highest requested line has been read, and a file shorter than a
+    /// requested line simply yields nothing for it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Prints the first and third lines of each file.
+    /// for line in diamond_op::new().select_lines(&[1, 3]) {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn select_lines(mut self, numbers: &[u64]) -> impl Iterator<Item = io::Result<String>> {
+        let mut numbers = numbers.to_vec();
+        numbers.sort_unstable();
+        numbers.dedup();
+        let last = numbers.last().copied().unwrap_or_default();
+        iter::from_fn(move || {
+            if last == 0 {
+                return None;
             }
-            if !self.prepare_next()? {
-                return Ok(0);
+            loop {
+                let line = match self.next_line() {
+                    Ok(line) => line?,
+                    Err(e) => return Some(Err(e)),
+                };
+                let number = self.cur_records;
+                if number >= last {
+                    // skips the rest of the current source, leaving the next one to be opened by
+                    // the next read so that this line is yielded before any error in opening it
+                    self.close_current();
+                }
+                if !line.is_empty() && numbers.binary_search(&number).is_ok() {
+                    return Some(Ok(line));
+                }
             }
-        }
+        })
     }
 
-    fn prepare_next(&mut self) -> io::Result<bool> {
-        self.cur_file = None;
-        self.cur_arg = self.args.next();
-        if let Some(arg) = self.cur_arg.as_deref() {
-            self.cur_file = Some(Reader::open(arg)?);
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+    /// Returns an iterator over the lines of all files and standard input, each paired with a key
+    /// derived by `key_fn`.
+    ///
+    /// `key_fn` receives each line without its trailing newline byte, so that lines can be
+    /// compared under a user-defined equivalence, e.g. ignoring case or trailing whitespace, while
+    /// the original line is yielded as is for output.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Prints the first line of each case-insensitive group.
+    /// let mut seen = std::collections::HashSet::new();
+    /// for pair in diamond_op::new().normalize_for(str::to_lowercase) {
+    ///     let (key, line) = pair?;
+    ///     if seen.insert(key) {
+    ///         print!("{}", line);
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn normalize_for<T>(
+        self,
+        key_fn: impl Fn(&str) -> T,
+    ) -> impl Iterator<Item = io::Result<(T, String)>> {
+        let term = self.config.line_terminator;
+        self.line_iter().map(move |line| {
+            let line = line?;
+            let key = key_fn(strip_terminator(&line, term));
+            Ok((key, line))
+        })
     }
-}
-
-/// A command line argument iterator that returns "-" if none is given.
-#[derive(Debug, Default)]
-struct Args(Option<iter::Fuse<env::ArgsOs>>);
 
-impl Iterator for Args {
-    type Item = ffi::OsString;
+    /// Returns an iterator over multi-line records of all files and standard input, each of which
+    /// starts at a line for which `starts_record` returns `true`.
+    ///
+    /// Lines are accumulated into the current record until a line that satisfies `starts_record`
+    /// is found; that line belongs to the new record, not to the one it ends. The end of each file
+    /// or standard input also ends the current record, and the lines preceding the first matching
+    /// line of a source form a record of their own. `starts_record` receives each line without its
+    /// trailing newline byte, while the records retain the newline bytes of their lines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Groups log lines, e.g. a stack trace, with the preceding timestamped line.
+    /// let starts_with_digit = |line: &str| line.starts_with(|c: char| c.is_ascii_digit());
+    /// for record in diamond_op::new().records_until(starts_with_digit) {
+    ///     print!("--\n{}", record?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn records_until(
+        mut self,
+        mut starts_record: impl FnMut(&str) -> bool,
+    ) -> impl Iterator<Item = io::Result<String>> {
+        let term = self.config.line_terminator;
+        let mut pending: Option<(usize, String)> = None; // (`args_taken` of the source, record)
+        let mut done = false;
+        iter::from_fn(move || {
+            while !done {
+                match self.next_line() {
+                    Ok(Some(line)) => {
+                        let source = self.args_taken;
+                        let starts = starts_record(strip_terminator(&line, term));
+                        match &mut pending {
+                            Some((s, record)) if *s == source && !starts => record.push_str(&line),
+                            _ => {
+                                if let Some((_, record)) = pending.replace((source, line)) {
+                                    return Some(Ok(record));
+                                }
+                            }
+                        }
+                    }
+                    Ok(None) => done = true,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            pending.take().map(|(_, record)| Ok(record))
+        })
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(args) = &mut self.0 {
-            args.next()
-        } else {
-            let mut args = env::args_os().fuse();
-            args.next(); // skip program name
-            self.0.insert(args).next().or_else(|| Some("-".into()))
-        }
+    /// Returns an iterator over the lines of all files and standard input with comments starting at
+    /// `marker` removed, as in many configuration files.
+    ///
+    /// A comment extends from an unescaped `marker` to the end of the line. Lines that consist only
+    /// of a comment and whitespace are dropped entirely, while the other lines are yielded with
+    /// the comment and the whitespace before it removed, keeping the newline byte. A `marker`
+    /// preceded by a backslash does not start a comment and is yielded without the backslash.
+    /// Quoted strings are not recognized, so a `marker` in quotes also starts a comment.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().strip_comments('#') {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn strip_comments(self, marker: char) -> impl Iterator<Item = io::Result<String>> {
+        let term = self.config.line_terminator;
+        self.line_iter().filter_map(move |line| {
+            let line = match line {
+                Ok(line) if line.is_empty() => return None, // skips the empty source marker
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            let body = strip_terminator(&line, term);
+            let mut out = String::with_capacity(line.len());
+            let mut chars = body.chars();
+            let mut commented = false;
+            while let Some(c) = chars.next() {
+                if c == '\\' && chars.clone().next() == Some(marker) {
+                    out.push(marker);
+                    chars.next();
+                } else if c == marker {
+                    commented = true;
+                    break;
+                } else {
+                    out.push(c);
+                }
+            }
+            if commented {
+                if out.trim().is_empty() {
+                    return None;
+                }
+                out.truncate(out.trim_end().len());
+            }
+            out.push_str(&line[body.len()..]);
+            Some(Ok(out))
+        })
     }
-}
 
-#[derive(Debug)]
+    /// Returns an iterator over the paragraphs of all files and standard input, like Perl's
+    /// paragraph mode (`$/ = ""`).
+    ///
+    /// A paragraph is a run of lines separated by one or more blank lines, which are not included
+    /// in the paragraphs. By default, only an empty line (`"\n"`) is blank, as in Perl;
+    /// [`paragraph_blank_is_whitespace`](Self::paragraph_blank_is_whitespace) makes lines that
+    /// consist only of whitespace, including `"\r\n"`, blank as well. The end of each file or
+    /// standard input also ends the current paragraph. The lines of a paragraph retain their
+    /// newline bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for paragraph in diamond_op::new().paragraph_iter() {
+    ///     print!("--\n{}", paragraph?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn paragraph_iter(mut self) -> impl Iterator<Item = io::Result<String>> {
+        let blank_is_whitespace = self.config.paragraph_blank_is_whitespace;
+        let term = self.config.line_terminator;
+        let mut pending: Option<(usize, String)> = None; // (`args_taken` of the source, paragraph)
+        let mut done = false;
+        iter::from_fn(move || {
+            while !done {
+                match self.next_line() {
+                    Ok(Some(line)) if line.is_empty() => {} // skips the empty source marker
+                    Ok(Some(line)) => {
+                        let source = self.args_taken;
+                        let line_body = strip_terminator(&line, term);
+                        let is_blank = match blank_is_whitespace {
+                            true => line_body.trim().is_empty(),
+                            false => line_body.is_empty(),
+                        };
+                        let ended = match &mut pending {
+                            Some((s, paragraph)) if *s == source => {
+                                if !is_blank {
+                                    paragraph.push_str(&line);
+                                    continue;
+                                }
+                                pending.take()
+                            }
+                            _ if is_blank => pending.take(),
+                            _ => pending.replace((source, line)),
+                        };
+                        if let Some((_, paragraph)) = ended {
+                            return Some(Ok(paragraph));
+                        }
+                    }
+                    Ok(None) => done = true,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            pending.take().map(|(_, paragraph)| Ok(paragraph))
+        })
+    }
+
+    /// Returns an iterator over the lines that satisfy `is_match` and their surrounding lines, like
+    /// `grep -B before -A after`.
+    ///
+    /// Each matching line is yielded with up to `before` preceding lines and up to `after`
+    /// following lines as context, all as [`ContextLine`]s that tell the matches from the context.
+    /// The contexts of nearby matches are merged so that no line is yielded twice, and a context
+    /// never extends across the boundary between files. `is_match` receives each line without its
+    /// trailing newline byte.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().grep_context(|line| line.contains("error"), 2, 2) {
+    ///     let line = line?;
+    ///     let sep = if line.is_match { ':' } else { '-' };
+    ///     print!("{}{}{}{}", line.source.to_string_lossy(), sep, line.line_number, sep);
+    ///     print!("{}", line.line);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn grep_context(
+        mut self,
+        mut is_match: impl FnMut(&str) -> bool,
+        before: usize,
+        after: usize,
+    ) -> impl Iterator<Item = io::Result<ContextLine>> {
+        let mut ring = collections::VecDeque::with_capacity(before);
+        let mut out = collections::VecDeque::new();
+        let mut source = 0; // `args_taken` of the current source
+        let mut after_left = 0;
+        iter::from_fn(move || {
+            loop {
+                if let Some(line) = out.pop_front() {
+                    return Some(Ok(line));
+                }
+                let line = match self.next_line().transpose()? {
+                    Ok(line) => line,
+                    Err(e) => return Some(Err(e)),
+                };
+                if source != self.args_taken {
+                    source = self.args_taken;
+                    ring.clear();
+                    after_left = 0;
+                }
+                let line = ContextLine {
+                    source: self.cur_arg.clone().unwrap_or_default(),
+                    line_number: self.cur_records,
+                    is_match: is_match(strip_terminator(&line, self.config.line_terminator)),
+                    line,
+                };
+                if line.is_match {
+                    out.extend(ring.drain(..));
+                    out.push_back(line);
+                    after_left = after;
+                } else if after_left > 0 {
+                    out.push_back(line);
+                    after_left -= 1;
+                } else if before > 0 {
+                    if ring.len() == before {
+                        ring.pop_front();
+                    }
+                    ring.push_back(line);
+                }
+            }
+        })
+    }
+
+    /// Returns an iterator over the lines of all files and standard input, suppressing consecutive
+    /// duplicate lines like `uniq`.
+    ///
+    /// Lines are compared without their trailing newline byte, and duplicates are collapsed across
+    /// file boundaries as if the files were concatenated (`cat a b | uniq`). The first line of each
+    /// run is yielded as is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().uniq_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn uniq_iter(self) -> impl Iterator<Item = io::Result<String>> {
+        self.uniq_iter_with(UniqOptions::default())
+    }
+
+    /// Returns an iterator over the lines of all files and standard input, suppressing consecutive
+    /// duplicate lines like `uniq` with the given options.
+    ///
+    /// This function works in the same way as [`uniq_iter`](Self::uniq_iter), except that lines
+    /// are compared ignoring case (`uniq -i`) and leading and trailing whitespace if requested by
+    /// `opts`, while the first line of each run is yielded as is. If [`UniqOptions::count`] is
+    /// set, each line is prefixed with the number of lines in its run like `uniq -c`, and is
+    /// yielded only after the end of the run has been read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use diamond_op::UniqOptions;
+    /// let opts = UniqOptions {
+    ///     ignore_case: true,
+    ///     count: true,
+    ///     ..Default::default()
+    /// };
+    /// for line in diamond_op::new().uniq_iter_with(opts) {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn uniq_iter_with(self, opts: UniqOptions) -> impl Iterator<Item = io::Result<String>> {
+        let term = self.config.line_terminator;
+        let mut lines = self.line_iter();
+        // the key of the current run, and the first line and length of it if counting
+        let mut run: Option<(String, Option<(String, u64)>)> = None;
+        iter::from_fn(move || {
+            loop {
+                let line = match lines.next() {
+                    Some(Ok(line)) if line.is_empty() => continue, // skips the empty source marker
+                    Some(Ok(line)) => line,
+                    Some(Err(e)) => return Some(Err(e)),
+                    None => {
+                        return run
+                            .take()
+                            .and_then(|(_, counted)| counted)
+                            .map(|run| Ok(UniqOptions::prefix_count(run)));
+                    }
+                };
+                let key = opts.key(strip_terminator(&line, term));
+                if let Some((last, counted)) = &mut run {
+                    if *last == key {
+                        if let Some((_, n)) = counted {
+                            *n += 1;
+                        }
+                        continue;
+                    }
+                }
+                if !opts.count {
+                    run = Some((key, None));
+                    return Some(Ok(line));
+                }
+                if let Some((_, Some(prev))) = run.replace((key, Some((line, 1)))) {
+                    return Some(Ok(UniqOptions::prefix_count(prev)));
+                }
+            }
+        })
+    }
+
+    /// Returns an iterator over the lines of all files and standard input, suppressing all the
+    /// duplicate lines like `sort -u` but preserving the order in which the lines first appear.
+    ///
+    /// Lines are compared without their trailing newline byte, and the first occurrence of each
+    /// line is yielded as is. Note that every distinct line is kept in memory; see
+    /// [`dedup_global_by_hash`](Self::dedup_global_by_hash) to bound the memory usage.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().dedup_global() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn dedup_global(self) -> impl Iterator<Item = io::Result<String>> {
+        let term = self.config.line_terminator;
+        let mut seen = collections::HashSet::new();
+        self.line_iter().filter(move |line| match line {
+            Ok(line) if line.is_empty() => false, // skips the empty source marker
+            Ok(line) => {
+                let key = strip_terminator(line, term);
+                !seen.contains(key) && seen.insert(key.to_owned())
+            }
+            Err(_) => true,
+        })
+    }
+
+    /// Returns an iterator over the lines of all files and standard input, suppressing all the
+    /// duplicate lines by their hash values.
+    ///
+    /// This function works in the same way as [`dedup_global`](Self::dedup_global), except that it
+    /// keeps only a 64-bit hash value of each distinct line. This reduces the memory usage at the
+    /// risk of dropping a line whose hash value happens to collide with that of another line.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().dedup_global_by_hash() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn dedup_global_by_hash(self) -> impl Iterator<Item = io::Result<String>> {
+        let term = self.config.line_terminator;
+        let state = hash::RandomState::new();
+        let mut seen = collections::HashSet::new();
+        self.line_iter().filter(move |line| match line {
+            Ok(line) if line.is_empty() => false, // skips the empty source marker
+            Ok(line) => seen.insert(hash::BuildHasher::hash_one(
+                &state,
+                strip_terminator(line, term),
+            )),
+            Err(_) => true,
+        })
+    }
+
+    /// Returns an iterator over the lines of all files and standard input in reverse order, like
+    /// `tac`.
+    ///
+    /// The sources are processed in reverse argument order, and the lines of each source are
+    /// yielded from the last to the first, as is, so the last line of a source that does not end
+    /// with a newline byte is concatenated with the following line on output, as in `tac`. Regular
+    /// files are read backwards in blocks from the end without being loaded as a whole, whereas
+    /// the other sources, including standard input, are read into memory first. Standard input is
+    /// read by this function before it returns, which fails if reading it fails; errors with the
+    /// other sources are yielded by the iterator. The callback set by
+    /// [`first_source_callback`](Self::first_source_callback) is still given the source of the
+    /// first argument, which is read last.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().reverse_line_iter()? {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn reverse_line_iter(mut self) -> io::Result<impl Iterator<Item = io::Result<String>>> {
+        let mut args = iter::from_fn(|| self.args.next(&self.config)).collect::<Vec<_>>();
+        if let Some(readers) = &mut self.named_readers {
+            // pairs each reader with its name as both are taken from the back
+            readers.make_contiguous().reverse();
+        } else if let Some(arg) = args.iter().find(|&arg| self.config.is_stdin(arg)) {
+            self.replay_stdin().map_err(|e| {
+                let name = self.hooks.source_name(&self.config, arg.clone());
+                DiamondError::wrap(ErrorPhase::Read, &name, e)
+            })?;
+            self.config.buffer_stdin = true;
+        }
+
+        let term = self.config.line_terminator;
+        // holds the hook back for the first argument, which is opened last
+        let mut first_source = self.hooks.first_source.take();
+        let mut cur: Option<(ffi::OsString, ReverseLines)> = None;
+        Ok(iter::from_fn(move || {
+            loop {
+                if let Some((name, lines)) = &mut cur {
+                    let ret = lines.next_line().and_then(|line| {
+                        line.map(String::from_utf8)
+                            .transpose()
+                            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+                    });
+                    match ret {
+                        Ok(Some(line)) => return Some(Ok(line)),
+                        Ok(None) => cur = None,
+                        Err(e) => {
+                            let e = DiamondError::wrap(ErrorPhase::Read, name, e);
+                            cur = None;
+                            return Some(Err(e));
+                        }
+                    }
+                }
+                let arg = args.pop()?;
+                if args.is_empty() {
+                    self.hooks.first_source = first_source.take();
+                }
+                let name = self.hooks.source_name(&self.config, arg.clone());
+                match self.open_reader(&arg) {
+                    Ok(Some(reader)) => match ReverseLines::new(reader, term) {
+                        Ok(lines) => cur = Some((name, lines)),
+                        Err(e) => return Some(Err(DiamondError::wrap(ErrorPhase::Read, &name, e))),
+                    },
+                    Ok(None) => {} // skipped by the symlink policy
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+        }))
+    }
+
+    /// Returns an iterator that yields one line from each file and standard input in turn.
+    ///
+    /// Unlike the other methods, this function opens all the sources when the first line is
+    /// requested and then reads them in rotation, dropping each source from the rotation once it
+    /// reaches EOF or fails to read. If any source fails to open, the iterator yields that error
+    /// and ends. Note that all the sources are kept open simultaneously, unless limited by
+    /// [`max_open_files`](Self::max_open_files), and that reading standard input may block the
+    /// rotation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().round_robin_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn round_robin_iter(mut self) -> impl Iterator<Item = io::Result<String>> {
+        let limit = self.config.max_open_files.unwrap_or(usize::MAX);
+        let mut readers = collections::VecDeque::new();
+        let mut failed = false;
+        iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            loop {
+                if let Err(e) = self.open_up_to(&mut readers, limit) {
+                    failed = true;
+                    return Some(Err(e));
+                }
+                let mut source = readers.pop_front()?;
+                let mut buf = String::new();
+                match source.read_line(&self.config, &mut buf) {
+                    Ok(0) => continue, // makes room for the next source
+                    Ok(_) => {
+                        readers.push_back(source);
+                        return Some(Ok(buf));
+                    }
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+        })
+    }
+
+    /// Returns an iterator that merges the lines of files and standard input, each sorted by
+    /// `cmp`, into a single sorted sequence, like `sort -m`.
+    ///
+    /// The lines are compared with the line terminators stripped and yielded as is, and equal
+    /// lines are yielded in argument order. Like [`round_robin_iter`](Self::round_robin_iter),
+    /// this function opens all the sources when the first line is requested and yields the error
+    /// and ends if any of them fails to open, whereas a source that fails to read is dropped after
+    /// the error is yielded. Only one line per source is held in memory at a time. The order is
+    /// unspecified if a source is not sorted by `cmp`. Note that all the sources are kept open
+    /// simultaneously and that reading standard input may block the other sources.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().merge_sorted(str::cmp) {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn merge_sorted<F>(mut self, cmp: F) -> impl Iterator<Item = io::Result<String>>
+    where
+        F: Fn(&str, &str) -> cmp::Ordering,
+    {
+        let cmp = rc::Rc::new(cmp);
+        let mut readers: Option<Vec<OpenSource>> = None;
+        let mut heap = collections::BinaryHeap::new();
+        let mut errors = collections::VecDeque::new();
+        iter::from_fn(move || {
+            let readers = match &mut readers {
+                Some(readers) => readers,
+                None => match self.open_all() {
+                    Ok(opened) => {
+                        let readers = readers.insert(opened.into());
+                        for (index, source) in readers.iter_mut().enumerate() {
+                            match next_merge_line(source, index, &self.config, &cmp) {
+                                Ok(next) => heap.extend(next),
+                                Err(e) => errors.push_back(e),
+                            }
+                        }
+                        readers
+                    }
+                    Err(e) => {
+                        readers = Some(Vec::new());
+                        return Some(Err(e));
+                    }
+                },
+            };
+            if let Some(e) = errors.pop_front() {
+                return Some(Err(e));
+            }
+            let MergeLine { line, index, .. } = heap.pop()?;
+            match next_merge_line(&mut readers[index], index, &self.config, &cmp) {
+                Ok(next) => heap.extend(next),
+                Err(e) => errors.push_back(e),
+            }
+            Some(Ok(line))
+        })
+    }
+
+    /// Returns an iterator that yields, at each step, the next line of every file and standard
+    /// input, like `paste`.
+    ///
+    /// Each item has one element per source in argument order, which is `None` if the source has
+    /// already reached EOF, and the iterator ends when all the sources have been exhausted. Like
+    /// [`round_robin_iter`](Self::round_robin_iter), this function opens all the sources at once
+    /// and yields the error and ends if any of them fails to open. A source that fails to read is
+    /// treated as ended after the error is yielded. Note that reading standard input may block the
+    /// other sources.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for row in diamond_op::new().zip_sources() {
+    ///     let row = row?
+    ///         .into_iter()
+    ///         .map(|line| line.unwrap_or_default().trim_end().to_owned())
+    ///         .collect::<Vec<_>>();
+    ///     println!("{}", row.join("\t"));
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn zip_sources(mut self) -> impl Iterator<Item = io::Result<Vec<Option<String>>>> {
+        let mut readers: Option<Vec<Option<OpenSource>>> = None;
+        let mut pending_row = None;
+        iter::from_fn(move || {
+            if let Some(row) = pending_row.take() {
+                return Some(Ok(row));
+            }
+            let readers = match &mut readers {
+                Some(readers) => readers,
+                None => match self.open_all() {
+                    Ok(opened) => readers.insert(opened.into_iter().map(Some).collect()),
+                    Err(e) => {
+                        readers = Some(Vec::new());
+                        return Some(Err(e));
+                    }
+                },
+            };
+
+            let mut error = None;
+            let mut row = Vec::with_capacity(readers.len());
+            for slot in readers.iter_mut() {
+                let mut buf = String::new();
+                let line = match slot {
+                    Some(source) => match source.read_line(&self.config, &mut buf) {
+                        Ok(0) => None,
+                        Ok(_) => Some(buf),
+                        Err(e) => {
+                            error.get_or_insert(e);
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                if line.is_none() {
+                    *slot = None;
+                }
+                row.push(line);
+            }
+
+            let has_line = row.iter().any(Option::is_some);
+            match error {
+                Some(e) => {
+                    pending_row = has_line.then_some(row);
+                    Some(Err(e))
+                }
+                None => has_line.then_some(Ok(row)),
+            }
+        })
+    }
+
+    /// Returns an iterator over the results of applying `f` to the lines of all files and standard
+    /// input in parallel, in the original order of the lines.
+    ///
+    /// The lines are read, as yielded by [`line_iter`](Self::line_iter), by a background thread,
+    /// which hands each line with its sequence number to a pool of `workers` threads applying `f`,
+    /// so that reading overlaps with the processing. The results are put back in the original
+    /// order before being yielded. The lines in flight, from being read to being yielded, are
+    /// limited to a fixed window proportional to `workers`, which bounds the memory used for
+    /// reordering and makes the reading wait while a slow line holds the window. This is useful
+    /// for CPU-heavy transforms whose output must keep the input order. An error reading a line is
+    /// yielded in the position of the line, and a panic in `f` is propagated to the caller. The
+    /// threads exit once the iterator is exhausted or dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let lines = diamond_op::new().par_map_lines(4, |line| Ok(line.to_uppercase()));
+    /// for line in lines {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn par_map_lines<T: Send + 'static>(
+        self,
+        workers: usize,
+        f: impl Fn(String) -> io::Result<T> + Send + Sync + 'static,
+    ) -> impl Iterator<Item = io::Result<T>> {
+        let workers = workers.max(1);
+        let window = workers * 16;
+        let (job_tx, job_rx) = mpsc::sync_channel::<(u64, String)>(workers);
+        let (result_tx, result_rx) = mpsc::channel::<(u64, thread::Result<io::Result<T>>)>();
+        let (token_tx, token_rx) = mpsc::sync_channel(window);
+        for _ in 0..window {
+            token_tx
+                .send(())
+                .expect("token channel has room for the window");
+        }
+
+        let job_rx = sync::Arc::new(sync::Mutex::new(job_rx));
+        let f = sync::Arc::new(f);
+        for _ in 0..workers {
+            let (job_rx, result_tx, f) = (job_rx.clone(), result_tx.clone(), f.clone());
+            thread::spawn(move || {
+                loop {
+                    let job = job_rx.lock().expect("job queue poisoned").recv();
+                    let Ok((seq, line)) = job else {
+                        return; // the reader has finished
+                    };
+                    let ret = panic::catch_unwind(panic::AssertUnwindSafe(|| f(line)));
+                    if result_tx.send((seq, ret)).is_err() {
+                        return; // the iterator has been dropped
+                    }
+                }
+            });
+        }
+        thread::spawn(move || {
+            let mut lines = self.line_iter();
+            for seq in 0.. {
+                if token_rx.recv().is_err() {
+                    return; // the iterator has been dropped
+                }
+                let sent = match lines.next() {
+                    Some(Ok(line)) => job_tx.send((seq, line)).is_ok(),
+                    Some(Err(e)) => result_tx.send((seq, Ok(Err(e)))).is_ok(),
+                    None => return,
+                };
+                if !sent {
+                    return;
+                }
+            }
+        });
+
+        let mut next_seq = 0;
+        let mut reordered = collections::HashMap::<u64, thread::Result<io::Result<T>>>::new();
+        iter::from_fn(move || {
+            loop {
+                if let Some(ret) = reordered.remove(&next_seq) {
+                    next_seq += 1;
+                    let _ = token_tx.send(()); // lets the reader proceed
+                    return Some(ret.unwrap_or_else(|payload| panic::resume_unwind(payload)));
+                }
+                let (seq, ret) = result_rx.recv().ok()?;
+                reordered.insert(seq, ret);
+            }
+        })
+    }
+
+    /// Returns an iterator over the first line of each file and standard input, paired with its
+    /// command line argument, like `head -n 1`.
+    ///
+    /// Only the first line of each source is read, and the rest is skipped without being read,
+    /// which is much faster than reading whole files to survey their headers. Empty sources are
+    /// skipped unless [`yield_empty_sources`](Self::yield_empty_sources) is set, in which case they
+    /// yield an empty line.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for item in diamond_op::new().first_line_per_file_iter() {
+    ///     let (arg, line) = item?;
+    ///     print!("{}: {}", arg.to_string_lossy(), line);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn first_line_per_file_iter(
+        mut self,
+    ) -> impl Iterator<Item = io::Result<(ffi::OsString, String)>> {
+        let term = self.config.line_terminator;
+        iter::from_fn(move || {
+            loop {
+                match self.prepare_next() {
+                    Ok(true) => {}
+                    Ok(false) => return None,
+                    Err(e) => return Some(Err(e)),
+                }
+                let reader = self.cur_file.as_mut()?;
+                let mut line = String::new();
+                match read_line(reader.as_buf_read_mut(), term, &mut line) {
+                    Ok(0) if !self.config.yield_empty_sources => {}
+                    Ok(n) => {
+                        self.add_record(n);
+                        return Some(Ok((self.cur_arg.clone().unwrap_or_default(), line)));
+                    }
+                    Err(e) => {
+                        if let Err(e) = self.skip_read_error(read_error(&self.cur_arg, e)) {
+                            return Some(Err(e));
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Returns an iterator over the whole contents of all files and standard input as bytes, each
+    /// paired with its command line argument.
+    ///
+    /// Unlike [`reader`](Self::reader), which reads all the sources as a single stream, this
+    /// function yields each source separately, including empty ones, without any UTF-8 validation,
+    /// which is useful for per-file processing of binary data such as hashing. Standard input is
+    /// yielded under its argument, which is "-" by default. Each source is read into memory as a
+    /// whole, so the memory usage is proportional to the size of the largest source.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for item in diamond_op::new().file_bytes_iter() {
+    ///     let (arg, content) = item?;
+    ///     let sum = content.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    ///     println!("{:02x}  {}", sum, arg.to_string_lossy());
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn file_bytes_iter(mut self) -> impl Iterator<Item = io::Result<(ffi::OsString, Vec<u8>)>> {
+        iter::from_fn(move || {
+            loop {
+                match self.prepare_next() {
+                    Ok(true) => {}
+                    Ok(false) => return None,
+                    Err(e) => return Some(Err(e)),
+                }
+                let reader = self.cur_file.as_mut()?;
+                let mut content = Vec::new();
+                match io::Read::read_to_end(reader, &mut content) {
+                    Ok(n) => {
+                        self.add_record(n);
+                        return Some(Ok((self.cur_arg.clone().unwrap_or_default(), content)));
+                    }
+                    Err(e) => {
+                        if let Err(e) = self.skip_read_error(read_error(&self.cur_arg, e)) {
+                            return Some(Err(e));
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Reads all files in parallel and returns their contents in the original argument order.
+    ///
+    /// Files are read by a pool of threads sized by [`thread::available_parallelism`], while
+    /// standard input is read by the calling thread before the files. Each item holds the argument
+    /// and the whole content of a source, or the error that occurred while opening or reading it.
+    /// Since every source is buffered in memory, this function is intended for moderately sized
+    /// inputs. [`on_file_handle`](Self::on_file_handle) is not invoked for the files read by the
+    /// pool.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for item in diamond_op::new().par_files_ordered() {
+    ///     let (arg, content) = item?;
+    ///     println!("{}: {} bytes", arg.to_string_lossy(), content.len());
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn par_files_ordered(mut self) -> Vec<io::Result<(ffi::OsString, Vec<u8>)>> {
+        let args = iter::from_fn(|| self.args.next(&self.config)).collect::<Vec<_>>();
+        let mut results = Vec::with_capacity(args.len());
+        let injected = self.named_readers.is_some();
+        for (i, arg) in args.iter().enumerate() {
+            if self.config.is_stdin(arg) || injected {
+                let content = match self.open_reader(arg) {
+                    Ok(reader) => reader.map(|mut reader| read_to_end(&mut reader)),
+                    Err(e) => Some(Err((ErrorPhase::Open, e))),
+                };
+                results.push((i, content));
+            }
+        }
+
+        let next = sync::atomic::AtomicUsize::new(0);
+        let workers = match injected {
+            true => 0, // the injected readers have all been read above
+            false => thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+        let (args, config) = (&args, &self.config);
+        thread::scope(|scope| {
+            let handles = (0..workers.min(args.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, sync::atomic::Ordering::Relaxed);
+                            let Some(arg) = args.get(i) else {
+                                return results;
+                            };
+                            if !config.is_stdin(arg) {
+                                results.push((i, read_file_to_end(arg, config)));
+                            }
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for handle in handles {
+                results.extend(handle.join().expect("worker thread panicked"));
+            }
+        });
+
+        results.sort_unstable_by_key(|(i, _)| *i);
+        results
+            .into_iter()
+            .filter_map(|(i, content)| {
+                let name = self.hooks.source_name(&self.config, args[i].clone());
+                let content = content?.map_err(|(phase, e)| DiamondError::wrap(phase, &name, e));
+                Some(content.map(|content| (name, content)))
+            })
+            .collect()
+    }
+
+    /// Returns a reader that reads bytes as a single stream.
+    ///
+    /// The returned reader reads bytes, treating all files and standard input as a consolidated
+    /// single stream and ignoring the EOF of each file or standard input in between, which is
+    /// different from the behavior of other methods in this type. Standard input is read ahead
+    /// through a buffer, so the bytes buffered but not consumed are lost when the reader is dropped
+    /// in the middle of standard input.
+    ///
+    /// # Examples
+    ///
+    /// Note that the example below uses [`BufRead::lines`] and thus works differently from
+    /// [`line_iter`](Self::line_iter).
+    ///
+    /// ```rust
+    /// use std::io::BufRead as _;
+    /// for line in diamond_op::new().reader().lines() {
+    ///     println!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn reader(self) -> impl BufRead {
+        SingleStreamReader::new(self, Vec::new())
+    }
+
+    /// Returns a reader that reads bytes as a single stream and calls `f` at each file boundary.
+    ///
+    /// The returned reader works in the same way as the one returned from
+    /// [`reader`](Self::reader), except that `f` is called with the argument name of each file or
+    /// standard input when the reader advances to it, including the first one. `f` is called
+    /// exactly once per source, even for an empty file that contributes no bytes to the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::BufRead as _;
+    /// let reader = diamond_op::new().reader_with_boundary_callback(|arg| {
+    ///     eprintln!("reading {}", arg.to_string_lossy());
+    /// });
+    /// for line in reader.lines() {
+    ///     println!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn reader_with_boundary_callback(
+        self,
+        f: impl FnMut(&ffi::OsStr) + 'static,
+    ) -> impl BufRead {
+        let mut reader = SingleStreamReader::new(self, Vec::new());
+        reader.on_boundary = Some(Box::new(f));
+        reader
+    }
+
+    /// Returns a reader that reads bytes as a single stream and copies them to `sink`, like `tee`.
+    ///
+    /// The returned reader works in the same way as the one returned from
+    /// [`reader`](Self::reader), except that the bytes are written to `sink` as they are consumed,
+    /// so that `sink` receives exactly the bytes that have been read. `sink` is flushed at the EOF
+    /// of all sources. An error writing to `sink` is returned from the subsequent read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::BufRead as _;
+    /// let mut log = Vec::new();
+    /// for line in diamond_op::new().tee(&mut log).lines() {
+    ///     println!("{}", line?);
+    /// }
+    /// eprintln!("consumed {} bytes", log.len());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn tee<W: io::Write>(self, sink: W) -> impl BufRead {
+        TeeReader {
+            inner: SingleStreamReader::new(self, Vec::new()),
+            sink,
+            error: None,
+        }
+    }
+
+    /// Returns an iterator over chunks of up to `n` bytes of all files and standard input read as a
+    /// single stream.
+    ///
+    /// The bytes are read through the reader returned from [`reader`](Self::reader), so every chunk
+    /// holds exactly `n` bytes regardless of file boundaries, except for the last one, which may be
+    /// shorter at the EOF of all sources. A chunk may also be short if an error occurs in the
+    /// middle of it, in which case the error is yielded next. The iterator yields an error of
+    /// [`io::ErrorKind::InvalidInput`] if `n` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for chunk in diamond_op::new().byte_chunks_iter(16) {
+    ///     let hex: Vec<_> = chunk?.iter().map(|b| format!("{:02x}", b)).collect();
+    ///     println!("{}", hex.join(" "));
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn byte_chunks_iter(self, n: usize) -> impl Iterator<Item = io::Result<Box<[u8]>>> {
+        let mut reader = SingleStreamReader::new(self, Vec::new());
+        let mut done = false;
+        iter::from_fn(move || {
+            if done {
+                return None;
+            } else if n == 0 {
+                done = true;
+                return Some(Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "chunk size must be positive",
+                )));
+            }
+            let mut chunk = vec![0; n];
+            match io::Read::read(&mut reader, &mut chunk) {
+                Ok(0) => {
+                    done = true;
+                    None
+                }
+                Ok(len) => {
+                    chunk.truncate(len);
+                    Some(Ok(chunk.into_boxed_slice()))
+                }
+                Err(e) => Some(Err(e)),
+            }
+        })
+    }
+
+    /// Returns an iterator over the lines of all files and standard input read as a single stream.
+    ///
+    /// Unlike [`line_iter`](Self::line_iter), the returned iterator reads lines from the reader
+    /// returned from [`reader`](Self::reader), so the last line of a file that does not end with a
+    /// newline byte is joined with the first line of the next file. As with `line_iter`, the
+    /// newline byte is not stripped from the end of each line.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().stream_line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn stream_line_iter(self) -> impl Iterator<Item = io::Result<String>> {
+        let term = self.config.line_terminator;
+        let mut reader = self.reader();
+        iter::from_fn(move || {
+            let mut buf = String::new();
+            match read_line(&mut reader, term, &mut buf) {
+                Ok(0) => None,
+                Ok(_) => Some(Ok(buf)),
+                Err(e) => Some(Err(e)),
+            }
+        })
+    }
+
+    /// Returns an iterator over the words of all files and standard input read as a single stream,
+    /// like `split_whitespace` applied to the whole input.
+    ///
+    /// A word is a maximal run of bytes other than ASCII whitespace, so line and file boundaries
+    /// only separate words if they are whitespace. Note that, as with [`reader`](Self::reader), a
+    /// word at the end of a file that does not end with whitespace is joined with the first word
+    /// of the next file. A word that is not valid UTF-8 is reported as an error of
+    /// [`io::ErrorKind::InvalidData`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut counts = std::collections::HashMap::new();
+    /// for word in diamond_op::new().word_iter() {
+    ///     *counts.entry(word?).or_insert(0) += 1;
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn word_iter(self) -> impl Iterator<Item = io::Result<String>> {
+        self.word_iter_by(|byte| byte.is_ascii_whitespace())
+    }
+
+    /// Returns an iterator over the words of all files and standard input read as a single stream,
+    /// separated by the bytes for which `is_separator` returns `true`.
+    ///
+    /// This function works in the same way as [`word_iter`](Self::word_iter) except for the
+    /// definition of separators.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for field in diamond_op::new().word_iter_by(|byte| byte == b',' || byte == b'\n') {
+    ///     println!("{}", field?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn word_iter_by(
+        self,
+        mut is_separator: impl FnMut(u8) -> bool,
+    ) -> impl Iterator<Item = io::Result<String>> {
+        let mut reader = self.reader();
+        iter::from_fn(move || {
+            let mut word = Vec::new();
+            loop {
+                let buf = match reader.fill_buf() {
+                    Ok([]) => break,
+                    Ok(buf) => buf,
+                    Err(e) => return Some(Err(e)),
+                };
+                let start = match word.is_empty() {
+                    true => buf
+                        .iter()
+                        .position(|&b| !is_separator(b))
+                        .unwrap_or(buf.len()),
+                    false => 0,
+                };
+                let end = buf[start..]
+                    .iter()
+                    .position(|&b| is_separator(b))
+                    .map_or(buf.len(), |n| start + n);
+                word.extend_from_slice(&buf[start..end]);
+                let at_separator = end < buf.len();
+                reader.consume(end);
+                if at_separator {
+                    break;
+                }
+            }
+            (!word.is_empty()).then(|| {
+                String::from_utf8(word).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            })
+        })
+    }
+
+    /// Returns a reader that reads bytes as a single stream, inserting `sep` between files.
+    ///
+    /// The returned reader works in the same way as the one returned from
+    /// [`reader`](Self::reader), except that it yields `sep` at each boundary between two files or
+    /// standard input, including empty ones, so that a downstream parser can find the boundaries.
+    /// `sep` is not inserted after the last file; chain it with [`BufRead::chain`] if needed. An
+    /// empty `sep` makes the reader equivalent to `reader`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read as _;
+    /// let mut buf = Vec::new();
+    /// diamond_op::new().reader_with_separator(b"\x0c".to_vec()).read_to_end(&mut buf)?;
+    /// for page in buf.split(|&b| b == b'\x0c') {
+    ///     print!("{}", String::from_utf8_lossy(page));
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn reader_with_separator(self, sep: Vec<u8>) -> impl BufRead {
+        SingleStreamReader::new(self, sep)
+    }
+
+    /// Reads and discards all the remaining bytes of all files and standard input, returning the
+    /// number of bytes discarded.
+    ///
+    /// This is useful to consume the input to EOF without processing it, e.g. to avoid a broken
+    /// pipe upstream. The bytes are discarded buffer by buffer without being split into lines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new();
+    /// let mut buf = String::new();
+    /// diamond.read_line(&mut buf)?; // processes only the first line
+    /// diamond.drain()?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn drain(&mut self) -> io::Result<u64> {
+        let mut total = 0;
+        loop {
+            if let Some(reader) = &mut self.cur_file {
+                let reader = reader.as_buf_read_mut();
+                match reader.fill_buf().map(|buf| buf.len()) {
+                    Ok(0) => {}
+                    Ok(n) => {
+                        reader.consume(n);
+                        self.add_bytes_yielded(n);
+                        total += n as u64;
+                        continue;
+                    }
+                    Err(e) => self.skip_read_error(read_error(&self.cur_arg, e))?,
+                }
+            }
+            if !self.prepare_next()? {
+                return Ok(total);
+            }
+        }
+    }
+
+    /// Reads up to and including the first occurrence of `needle` in all files and standard input
+    /// read as a single stream, returning the offset at which it starts or `None` at the EOF of all
+    /// sources.
+    ///
+    /// The offset counts all bytes read so far by this instance, as reported by
+    /// [`bytes_read`](Self::bytes_read), so an occurrence that continues into the next file is
+    /// also found. The subsequent reads continue just past the occurrence.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if `needle` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Prints the lines following the marker.
+    /// let mut diamond = diamond_op::new();
+    /// if diamond.find(b"-- BEGIN --\n")?.is_some() {
+    ///     let mut buf = String::new();
+    ///     while diamond.read_line_replacing(&mut buf)? != 0 {
+    ///         print!("{}", buf);
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn find(&mut self, needle: &[u8]) -> io::Result<Option<u64>> {
+        check_needle(needle)?;
+        // the length of the longest proper prefix of `needle[..=i]` that is also its suffix
+        let mut fallback = vec![0; needle.len()];
+        let mut k = 0;
+        for i in 1..needle.len() {
+            while k > 0 && needle[i] != needle[k] {
+                k = fallback[k - 1];
+            }
+            if needle[i] == needle[k] {
+                k += 1;
+            }
+            fallback[i] = k;
+        }
+
+        let mut matched = 0;
+        loop {
+            if let Some(reader) = &mut self.cur_file {
+                let reader = reader.as_buf_read_mut();
+                match reader.fill_buf() {
+                    Ok([]) => {}
+                    Ok(buf) => {
+                        let mut found = None;
+                        for (i, &byte) in buf.iter().enumerate() {
+                            while matched > 0 && byte != needle[matched] {
+                                matched = fallback[matched - 1];
+                            }
+                            if byte == needle[matched] {
+                                matched += 1;
+                            }
+                            if matched == needle.len() {
+                                found = Some(i + 1);
+                                break;
+                            }
+                        }
+                        let n = found.unwrap_or(buf.len());
+                        reader.consume(n);
+                        self.add_bytes_yielded(n);
+                        if found.is_some() {
+                            return Ok(Some(self.bytes_read - needle.len() as u64));
+                        }
+                        continue;
+                    }
+                    Err(e) => self.skip_read_error(read_error(&self.cur_arg, e))?,
+                }
+            }
+            if !self.prepare_next()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Reads all files and standard input and reports the line terminators used in each of them.
+    ///
+    /// Returns a list of the arguments and their [`LineEndingStats`] in the order they are read.
+    /// The sources are scanned buffer by buffer without being split into lines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for (arg, stats) in diamond_op::new().line_ending_report()? {
+    ///     if stats.crlf > 0 && stats.lf > 0 {
+    ///         eprintln!("{}: mixed line endings", arg.to_string_lossy());
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn line_ending_report(mut self) -> io::Result<Vec<(ffi::OsString, LineEndingStats)>> {
+        let mut report = Vec::new();
+        while self.prepare_next()? {
+            if let Some(reader) = &mut self.cur_file {
+                let stats = LineEndingStats::count(reader.as_buf_read_mut())
+                    .map_err(|e| read_error(&self.cur_arg, e))?;
+                report.push((self.cur_arg.clone().unwrap_or_default(), stats));
+            }
+        }
+        Ok(report)
+    }
+
+    /// Counts the non-overlapping occurrences of `needle` in all files and standard input read as a
+    /// single stream, like `cat | grep -o | wc -l` for a fixed string.
+    ///
+    /// The sources are concatenated as by [`reader`](Self::reader), so an occurrence that starts at
+    /// the end of a file and continues into the next file is counted. See
+    /// [`count_matches_per_file`](Self::count_matches_per_file) to count each source separately.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if `needle` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let count = diamond_op::new().count_matches(b"TODO")?;
+    /// println!("{}", count);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn count_matches(self, needle: &[u8]) -> io::Result<u64> {
+        count_matches(&mut self.reader(), needle)
+    }
+
+    /// Counts the non-overlapping occurrences of `needle` in each of the files and standard input.
+    ///
+    /// Returns a list of the arguments and their counts in the order they are read. Each source is
+    /// searched on its own, so an occurrence that straddles the boundary between two sources is
+    /// counted in neither of them, unlike [`count_matches`](Self::count_matches).
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if `needle` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for (arg, count) in diamond_op::new().count_matches_per_file(b"TODO")? {
+    ///     println!("{}:{}", arg.to_string_lossy(), count);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn count_matches_per_file(
+        mut self,
+        needle: &[u8],
+    ) -> io::Result<Vec<(ffi::OsString, u64)>> {
+        check_needle(needle)?;
+        let mut counts = Vec::new();
+        while self.prepare_next()? {
+            if let Some(reader) = &mut self.cur_file {
+                let count = count_matches(reader.as_buf_read_mut(), needle)
+                    .map_err(|e| read_error(&self.cur_arg, e))?;
+                counts.push((self.cur_arg.clone().unwrap_or_default(), count));
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Closes the current file or standard input, even if it has not reached EOF, and opens the
+    /// next one, returning its argument or `None` if all the files have been read.
+    ///
+    /// This allows the caller to abandon the rest of a file. If called before the first line is
+    /// read, this function opens the first source. Note that the subsequent reads still proceed to
+    /// the following sources at the EOF of the opened one. If standard input is abandoned, the
+    /// bytes already read ahead into its buffer are kept, and a later "-" argument continues from
+    /// where it was left.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Prints the first line of each file like `head -n 1 -q`.
+    /// let mut diamond = diamond_op::new();
+    /// let mut buf = String::new();
+    /// while diamond.open_next()?.is_some() {
+    ///     diamond.read_line_replacing(&mut buf)?;
+    ///     print!("{}", buf);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn open_next(&mut self) -> io::Result<Option<&ffi::OsStr>> {
+        Ok(if self.prepare_next()? {
+            self.current_arg()
+        } else {
+            None
+        })
+    }
+
+    /// Seeks the file or standard input currently being processed back to its beginning.
+    ///
+    /// Standard input can be rewound only if [`buffer_stdin`](Self::buffer_stdin) is set;
+    /// otherwise, this function fails with [`io::ErrorKind::Unsupported`]. This function does
+    /// nothing before the first line has been read or after all the files have been read.
+    pub fn rewind(&mut self) -> io::Result<()> {
+        if let Some(reader) = &mut self.cur_file {
+            reader
+                .rewind(self.config.head_bytes_per_file)
+                .map_err(|e| read_error(&self.cur_arg, e))?;
+            self.cur_records = 0;
+            self.cur_bytes = 0;
+            self.cur_skipping = true;
+            if let Some((_, start)) = &mut self.cur_offset {
+                *start = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the command line argument currently being processed or `None` before the first line
+    /// has been read or after all the files have been read.
+    pub fn current_arg(&self) -> Option<&ffi::OsStr> {
+        self.cur_arg.as_deref()
+    }
+
+    /// Returns the command line argument currently being processed as a `String`, replacing invalid
+    /// Unicode sequences with U+FFFD, or `None` before the first line has been read or after all
+    /// the files have been read.
+    ///
+    /// Standard input is reported as "-", or as rewritten by
+    /// [`map_source_name`](Self::map_source_name) if set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new();
+    /// while let Some(line) = diamond.next_line()? {
+    ///     print!("{}: {}", diamond.current_arg_lossy().unwrap(), line);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn current_arg_lossy(&self) -> Option<String> {
+        self.current_arg()
+            .map(|arg| arg.to_string_lossy().into_owned())
+    }
+
+    /// Returns the names of the command line arguments that are currently being processed or yet to
+    /// be processed, in the order they are read.
+    ///
+    /// The implicit standard input argument is included if no argument is given.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let diamond = diamond_op::new();
+    /// for name in diamond.source_names() {
+    ///     eprintln!("will read: {}", name.to_string_lossy());
+    /// }
+    /// ```
+    pub fn source_names(&self) -> Vec<ffi::OsString> {
+        let remaining = self.args.remaining(&self.config).into_iter();
+        let remaining = remaining.map(|arg| self.hooks.source_name(&self.config, arg));
+        let chained = self.chained.iter().flat_map(|d| d.source_names());
+        self.cur_arg
+            .iter()
+            .cloned()
+            .chain(remaining)
+            .chain(chained)
+            .collect()
+    }
+
+    /// Consumes the `Diamond`, returning the bytes of the current source buffered but not consumed
+    /// yet and the command line arguments not opened yet.
+    ///
+    /// This is useful to hand off the rest of the input to another processor after stopping early.
+    /// The first element is `None` if no file or standard input is open. It holds only the bytes
+    /// already read into the buffer, and the rest of the current source is lost when it is dropped;
+    /// for standard input, this buffer is the only way to recover the bytes read ahead. The
+    /// arguments are returned as given, not as rewritten by
+    /// [`map_source_name`](Self::map_source_name), followed by those of the instances added by
+    /// [`chain`](Self::chain).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Write as _;
+    /// let mut diamond = diamond_op::new();
+    /// let header = diamond.next_line()?;
+    /// let (buffered, remaining) = diamond.into_remaining();
+    /// std::io::stdout().write_all(&buffered.unwrap_or_default())?;
+    /// eprintln!("not opened: {:?}", remaining);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn into_remaining(self) -> (Option<Vec<u8>>, Vec<ffi::OsString>) {
+        let buffered = self.cur_file.as_ref().map(|r| r.buffer().to_vec());
+        let remaining = self.chain_iter().flat_map(|d| d.args.remaining(&d.config));
+        (buffered, remaining.collect())
+    }
+
+    /// Returns the sources that are going to be read, in the order in which they are read, without
+    /// opening them.
+    ///
+    /// This function lists the remaining command line arguments, or standard input if none is
+    /// given, and classifies each by its file system metadata, omitting the symbolic links that
+    /// [`SymlinkPolicy::Skip`] skips, which is useful to preview and debug the set of inputs. The
+    /// sources of the instances added by [`chain`](Self::chain) follow, and the source currently
+    /// being read is not included. This function fails if the metadata of a file
+    /// cannot be read for a reason other than its nonexistence.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for source in diamond_op::new().plan()? {
+    ///     eprintln!("{}: {:?}", source.order, source.arg);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn plan(&self) -> io::Result<Vec<SourcePlan>> {
+        let mut plan = Vec::new();
+        let remaining = self.chain_iter().flat_map(|d| {
+            d.args
+                .remaining(&d.config)
+                .into_iter()
+                .map(move |arg| (d, arg))
+        });
+        for (d, arg) in remaining {
+            let resolved = if d.named_readers.is_some() {
+                SourceKind::Other
+            } else if d.config.is_stdin(&arg) {
+                SourceKind::Stdin
+            } else if d.config.symlinks != SymlinkPolicy::Follow
+                && fs::symlink_metadata(&arg).is_ok_and(|m| m.file_type().is_symlink())
+            {
+                match d.config.symlinks {
+                    SymlinkPolicy::Skip => continue,
+                    _ => SourceKind::Symlink,
+                }
+            } else {
+                match fs::metadata(&arg) {
+                    Ok(m) if m.is_file() => SourceKind::File,
+                    Ok(m) if m.is_dir() => SourceKind::Directory,
+                    Ok(_) => SourceKind::Other,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => SourceKind::Missing,
+                    Err(e) => return Err(DiamondError::wrap(ErrorPhase::Open, &arg, e)),
+                }
+            };
+            plan.push(SourcePlan {
+                arg: d.hooks.source_name(&d.config, arg),
+                resolved,
+                order: plan.len(),
+            });
+        }
+        Ok(plan)
+    }
+
+    /// Returns the cumulative counters of reading, which are useful to monitor a long-running
+    /// process.
+    ///
+    /// The [`Metrics`] accumulate over all the sources read so far, unlike the position of the
+    /// current source such as [`current_index`](Self::current_index). The timing covers only the
+    /// calls that read from the sources, so that it tells whether a run is bound by I/O.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new();
+    /// while let Some(line) = diamond.next_line()? {
+    ///     print!("{}", line);
+    /// }
+    /// let metrics = diamond.metrics();
+    /// eprintln!("{} lines in {:?}", metrics.records, metrics.total_read_time);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            bytes: self.bytes_read,
+            ..self.metrics
+        }
+    }
+
+    /// Returns a `Diamond` that reads the sources of `other` after those of `self`, like
+    /// [`Iterator::chain`].
+    ///
+    /// The sources of both are read in sequence with their boundaries preserved, and
+    /// [`current_arg`](Self::current_arg) reports the sources of `other` once those of `self` are
+    /// exhausted, while the counters such as [`bytes_read`](Self::bytes_read) continue to
+    /// accumulate. The options and hooks of each apply to its own sources; in particular, the
+    /// [`on_eof`](Self::on_eof) hook of `self` is called before `other` starts. `other` is expected
+    /// not to have been read yet.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Reads the default settings before the files given by the command line arguments.
+    /// let defaults = diamond_op::from_list_file("defaults.lst");
+    /// # let defaults = Ok::<_, std::io::Error>(diamond_op::new());
+    /// for line in defaults?.chain(diamond_op::new()).line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn chain(mut self, other: Diamond) -> Self {
+        self.chained = Some(Box::new(match self.chained.take() {
+            Some(next) => next.chain(other),
+            None => other,
+        }));
+        self
+    }
+
+    /// Returns the total number of bytes read so far from all files and standard input.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Returns an estimate of the total number of bytes of all files, including those already read,
+    /// or `None` if the size of any source is unknown.
+    ///
+    /// The already read sources count for the bytes actually read from them, and the others count
+    /// for their sizes reported by the file system. The size is unknown if a source yet to be read
+    /// is standard input or is not a regular file. Combined with [`bytes_read`](Self::bytes_read),
+    /// this is useful to show the progress, but note that it is just an estimate if files change
+    /// while being read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new();
+    /// let mut buf = String::new();
+    /// while diamond.read_line_replacing(&mut buf)? != 0 {
+    ///     if let Some(total) = diamond.total_bytes()? {
+    ///         eprint!("\r{}%", diamond.bytes_read() * 100 / total.max(1));
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn total_bytes(&self) -> io::Result<Option<u64>> {
+        let size_of = |d: &Self, metadata: fs::Metadata| {
+            let limit = d.config.head_bytes_per_file.unwrap_or(u64::MAX);
+            metadata.is_file().then(|| metadata.len().min(limit))
+        };
+        if self.chain_iter().any(|d| d.named_readers.is_some()) {
+            return Ok(None);
+        }
+
+        let mut total = self.bytes_read - self.cur_bytes;
+        if let Some(reader) = &self.cur_file {
+            match reader.file() {
+                Some(file) => match size_of(self, file.metadata()?) {
+                    Some(size) => total += size,
+                    None => return Ok(None),
+                },
+                None => return Ok(None),
+            }
+        }
+        for d in self.chain_iter() {
+            for arg in d.args.remaining(&d.config) {
+                if d.config.is_stdin(&arg) {
+                    return Ok(None);
+                }
+                match size_of(d, fs::metadata(&arg)?) {
+                    Some(size) => total += size,
+                    None => return Ok(None),
+                }
+            }
+        }
+        Ok(Some(total))
+    }
+
+    /// Returns the 0-based position of the command line argument currently being processed or
+    /// `None` before the first line has been read or after all the files have been read.
+    ///
+    /// The implicit standard input used when no argument is given is at position 0.
+    pub fn current_index(&self) -> Option<usize> {
+        self.cur_arg.as_ref().map(|_| self.args_taken - 1)
+    }
+
+    /// Returns whether the source currently being processed is standard input, or `None` before the
+    /// first line has been read or after all the files have been read.
+    pub fn is_stdin(&self) -> Option<bool> {
+        self.cur_file.as_ref().map(Reader::is_stdin)
+    }
+
+    /// Returns whether the source currently being processed is the last one, or `None` before the
+    /// first line has been read or after all the files have been read.
+    ///
+    /// A source is the last one if no command line argument remains after it, regardless of
+    /// whether the remaining arguments can be opened.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new();
+    /// let mut buf = String::new();
+    /// while diamond.read_line_replacing(&mut buf)? != 0 {
+    ///     print!("{}", buf);
+    ///     if diamond.is_last_source() == Some(true) {
+    ///         eprintln!("(reading the last source)");
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn is_last_source(&self) -> Option<bool> {
+        let chained = self.chained.as_deref();
+        let is_last = self.args.is_empty() && chained.is_none_or(|d| d.source_names().is_empty());
+        self.cur_arg.as_ref().map(|_| is_last)
+    }
+
+    /// Makes each empty file or standard input contribute a single empty record.
+    ///
+    /// By default, an empty source yields no records and is skipped silently. With this option,
+    /// [`line_iter`](Self::line_iter) and the other iterators yield an empty `String` for each
+    /// empty source, so that per-file bookkeeping can observe it. [`read_line`](Self::read_line)
+    /// and [`read_until`](Self::read_until) are unaffected because a zero-length read there means
+    /// EOF. The iterators that derive their items from the lines, i.e.
+    /// [`line_batches`](Self::line_batches), [`paragraph_iter`](Self::paragraph_iter),
+    /// [`strip_comments`](Self::strip_comments), [`uniq_iter`](Self::uniq_iter),
+    /// [`uniq_iter_with`](Self::uniq_iter_with), [`dedup_global`](Self::dedup_global), and
+    /// [`dedup_global_by_hash`](Self::dedup_global_by_hash), skip the empty records.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().yield_empty_sources().line_iter() {
+    ///     println!("{:?}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn yield_empty_sources(mut self) -> Self {
+        self.config.yield_empty_sources = true;
+        self
+    }
+
+    /// Registers a callback invoked with the argument and the underlying [`fs::File`] right after
+    /// each file is opened.
+    ///
+    /// This allows the caller to tune the file handle, e.g. to advise the kernel of sequential
+    /// access through `posix_fadvise`, without this crate depending on platform libraries. The
+    /// callback is not invoked for standard input.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let diamond = diamond_op::new().on_file_handle(|arg, file| {
+    ///     if let Ok(metadata) = file.metadata() {
+    ///         eprintln!("{}: {} bytes", arg.to_string_lossy(), metadata.len());
+    ///     }
+    /// });
+    /// for line in diamond.line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn on_file_handle(
+        mut self,
+        f: impl FnMut(&ffi::OsStr, &fs::File) + Send + 'static,
+    ) -> Self {
+        self.hooks.on_file_handle = Some(Box::new(f));
+        self
+    }
+
+    /// Sets the policy for file arguments that are symbolic links.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use diamond_op::SymlinkPolicy;
+    ///
+    /// for line in diamond_op::new().symlinks(SymlinkPolicy::Skip).line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.config.symlinks = policy;
+        self
+    }
+
+    /// Makes reads from standard input fail with [`io::ErrorKind::TimedOut`] if no data arrives
+    /// within `dur`.
+    ///
+    /// Standard input is read by a background thread while this option is set, so that a blocking
+    /// read can be abandoned. A read that has timed out can be retried. Reads from files are
+    /// unaffected. Note that the background thread stays blocked until standard input receives
+    /// data or is closed, even after the `Diamond` is dropped. Standard input is locked only while
+    /// the thread is reading it, so other code can lock it once the thread has finished.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// let diamond = diamond_op::new().stdin_timeout(Duration::from_secs(5));
+    /// for line in diamond.line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn stdin_timeout(mut self, dur: time::Duration) -> Self {
+        self.config.stdin_timeout = Some(dur);
+        self
+    }
+
+    /// Makes a failure to read from a file or standard input skip the rest of that source instead of
+    /// being returned as an error.
+    ///
+    /// The skipped errors are recorded and can be retrieved with
+    /// [`take_errors`](Self::take_errors). Note that a line that is not valid UTF-8 is also a read
+    /// error for the `String`-based methods. Errors on opening a source are not affected. By
+    /// default, read errors are returned to the caller to avoid losing data silently.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new().skip_read_errors();
+    /// let mut buf = String::new();
+    /// while diamond.read_line_replacing(&mut buf)? != 0 {
+    ///     print!("{}", buf);
+    /// }
+    /// for e in diamond.take_errors() {
+    ///     eprintln!("skipped: {}", e);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn skip_read_errors(mut self) -> Self {
+        self.config.skip_read_errors = true;
+        self
+    }
+
+    /// Returns the arguments of the sources that have been read to the end without yielding any
+    /// records, in the order they are read.
+    ///
+    /// This covers empty files, sources whose lines are all filtered out by
+    /// [`take_while_line`](Self::take_while_line) and [`skip_while_line`](Self::skip_while_line) or
+    /// consumed by [`first_source_callback`](Self::first_source_callback), and sources abandoned by
+    /// [`open_next`](Self::open_next) before anything is read from them. Sources are recorded when the reading proceeds past
+    /// them, so the last source is included only after the EOF of all sources has been reached.
+    /// Only the sources read one after another, e.g. by [`read_line`](Self::read_line), the line
+    /// iterators, and [`reader`](Self::reader), are recorded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new();
+    /// while let Some(line) = diamond.next_line()? {
+    ///     print!("{}", line);
+    /// }
+    /// for arg in diamond.empty_sources() {
+    ///     eprintln!("warning: {}: empty input", arg.to_string_lossy());
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn empty_sources(&self) -> &[ffi::OsString] {
+        &self.empty_sources
+    }
+
+    /// Returns the arguments of the sources cut short by
+    /// [`max_lines_per_file`](Self::max_lines_per_file), in the order they are read.
+    ///
+    /// A source is recorded when the reading proceeds past it with data remaining after the
+    /// maximum number of lines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new().max_lines_per_file(10_000);
+    /// while let Some(line) = diamond.next_line()? {
+    ///     print!("{}", line);
+    /// }
+    /// for arg in diamond.truncated_sources() {
+    ///     eprintln!("warning: {}: too many lines", arg.to_string_lossy());
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn truncated_sources(&self) -> &[ffi::OsString] {
+        &self.truncated_sources
+    }
+
+    /// Returns a copy of the options of this instance.
+    ///
+    /// See [`DiamondConfig`] for details.
+    pub fn config(&self) -> DiamondConfig {
+        self.config.clone()
+    }
+
+    /// Starts reading each file at the byte offset saved for its command line argument in
+    /// `offsets`, skipping the data processed in a previous run, like a resumable `tail`.
+    ///
+    /// The files not in `offsets` and standard input are read from the beginning. If a saved
+    /// offset is beyond the end of the file, which suggests that the file has been truncated or
+    /// replaced, the file is read from the beginning, and a warning is recorded to be retrieved by
+    /// [`take_errors`](Self::take_errors). Use [`current_offsets`](Self::current_offsets) to save
+    /// the offsets for the next run.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut offsets = std::collections::HashMap::new();
+    /// offsets.insert("app.log".into(), 1024);
+    /// let mut diamond = diamond_op::new().resume_from(&offsets);
+    /// while let Some(line) = diamond.next_line()? {
+    ///     print!("{}", line);
+    /// }
+    /// for warning in diamond.take_errors() {
+    ///     eprintln!("warning: {}", warning);
+    /// }
+    /// let offsets = diamond.current_offsets(); // saves these for the next run
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn resume_from(mut self, offsets: &collections::HashMap<ffi::OsString, u64>) -> Self {
+        self.resume_offsets = offsets.clone();
+        self
+    }
+
+    /// Returns the byte offsets up to which the files have been consumed, keyed by their command
+    /// line arguments.
+    ///
+    /// The map holds the files read so far, including the current one, and the offsets are
+    /// relative to the beginning of each file, so they can be passed to
+    /// [`resume_from`](Self::resume_from) in a later run to continue from where this run stopped.
+    /// Standard input and other sources that are not seekable files are not included.
+    pub fn current_offsets(&self) -> collections::HashMap<ffi::OsString, u64> {
+        let mut offsets = self.offsets.clone();
+        if let Some((arg, start)) = &self.cur_offset {
+            offsets.insert(arg.clone(), start + self.cur_bytes);
+        }
+        offsets
+    }
+
+    /// Returns the errors recorded so far by [`skip_read_errors`](Self::skip_read_errors), clearing
+    /// the record.
+    pub fn take_errors(&mut self) -> Vec<io::Error> {
+        mem::take(&mut self.errors)
+    }
+
+    /// Sets the buffer capacity used to read standard input.
+    ///
+    /// By default, standard input is read through a buffer of the default size of
+    /// [`io::BufReader`]. With this option, it is read through a buffer of `cap` bytes, which can be
+    /// made small to reduce latency when standard input is an interactive pipe. Files are
+    /// unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().stdin_capacity(512).line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn stdin_capacity(mut self, cap: usize) -> Self {
+        self.config.stdin_capacity = Some(cap);
+        self
+    }
+
+    /// Limits each file and standard input to its first `n` bytes, like `head -c` applied per file.
+    ///
+    /// A source is treated as if it reached EOF after `n` bytes, even in the middle of a line, so
+    /// a line cut at the limit is returned as the last line of that source. The limit applies to
+    /// the bytes of each source separately, and a repeated "-" argument reads the next `n` bytes of
+    /// standard input, including those read ahead into the buffer beyond the previous limit.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().head_bytes_per_file(1024).line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn head_bytes_per_file(mut self, n: u64) -> Self {
+        self.config.head_bytes_per_file = Some(n);
+        self
+    }
+
+    /// Limits each file and standard input to its first `n` lines, as a safeguard against a single
+    /// source dominating the processing.
+    ///
+    /// The reading proceeds to the next source once `n` lines, or records of the other functions
+    /// such as [`next_record`](Self::next_record), have been read from a source, skipping the rest
+    /// of it. The sources cut short are reported by
+    /// [`truncated_sources`](Self::truncated_sources). The limit applies to each source separately,
+    /// including the sources read side by side by [`round_robin_iter`](Self::round_robin_iter),
+    /// [`merge_sorted`](Self::merge_sorted), and [`zip_sources`](Self::zip_sources), and does not
+    /// affect [`reader`](Self::reader).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().max_lines_per_file(10_000).line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn max_lines_per_file(mut self, n: u64) -> Self {
+        self.config.max_lines_per_file = Some(n);
+        self
+    }
+
+    /// Makes each regular file check its length on reaching EOF and keep reading the data
+    /// appended since, so that a file written in bursts while being read is read as a whole.
+    ///
+    /// The check is made each time the reading reaches the EOF of a file, so a line not yet
+    /// terminated at EOF is completed by the data appended before the check. Unlike `tail -f`,
+    /// the reading proceeds to the next source as soon as a check finds that the file has not
+    /// grown; it does not wait for more data. A file found shorter than the position already read
+    /// fails to read with [`io::ErrorKind::InvalidData`]. The other sources are not affected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().stable_read().line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn stable_read(mut self) -> Self {
+        self.config.stable_read = true;
+        self
+    }
+
+    /// Reads standard input fully into memory when it is first opened, so that it can be rewound
+    /// and read multiple times.
+    ///
+    /// With this option, [`rewind`](Self::rewind) works for standard input, and each subsequent
+    /// "-" argument reads the same content again from memory. Note that the whole standard input is
+    /// kept in memory until the `Diamond` is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new().buffer_stdin();
+    /// let mut buf = String::new();
+    /// diamond.read_line_replacing(&mut buf)?; // peeks the first line
+    /// diamond.rewind()?;
+    /// while diamond.read_line_replacing(&mut buf)? != 0 {
+    ///     print!("{}", buf);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn buffer_stdin(mut self) -> Self {
+        self.config.buffer_stdin = true;
+        self
+    }
+
+    /// Registers a function that rewrites the argument names reported by
+    /// [`current_arg`](Self::current_arg), [`source_names`](Self::source_names), and errors.
+    ///
+    /// The function affects only the reported names; the files are still opened using the
+    /// original arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new()
+    ///     .map_source_name(|arg| if arg == "-" { "(stdin)".into() } else { arg.into() });
+    /// let mut buf = String::new();
+    /// while diamond.read_line_replacing(&mut buf)? != 0 {
+    ///     print!("{}: {}", diamond.current_arg().unwrap().to_string_lossy(), buf);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn map_source_name(
+        mut self,
+        f: impl Fn(&ffi::OsStr) -> ffi::OsString + Send + 'static,
+    ) -> Self {
+        self.hooks.map_source_name = Some(Box::new(f));
+        self
+    }
+
+    /// Makes [`current_arg`](Self::current_arg) and the other functions reporting source names
+    /// report the canonical absolute path of each file.
+    ///
+    /// The files are still opened using the original arguments. The original argument is reported
+    /// if it cannot be canonicalized, e.g., because the file does not exist, and standard input is
+    /// reported as is. The canonical path is passed to the function registered by
+    /// [`map_source_name`](Self::map_source_name), if any.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new().canonicalize_names();
+    /// let mut buf = String::new();
+    /// while diamond.read_line_replacing(&mut buf)? != 0 {
+    ///     print!("{}: {}", diamond.current_arg().unwrap().to_string_lossy(), buf);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn canonicalize_names(mut self) -> Self {
+        self.config.canonicalize_names = true;
+        self
+    }
+
+    /// Makes the line functions read each file and standard input only while `pred` returns
+    /// `true`, like [`Iterator::take_while`] applied to each source separately.
+    ///
+    /// The predicate is given each line without the line terminator. The first line for which it
+    /// returns `false` is not returned, and the reading proceeds to the next source, skipping the
+    /// rest of the current one. This applies to [`read_line`](Self::read_line),
+    /// [`next_line`](Self::next_line), and the functions built on them, such as
+    /// [`line_iter`](Self::line_iter), but not to the functions reading records or bytes. With
+    /// [`strict_bufread_compat`](Self::strict_bufread_compat), a line joined across sources by
+    /// `read_line` is given to the predicate as a whole, in the state of the source it ends in.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Reads the header section of each file, which ends at a blank line.
+    /// let diamond = diamond_op::new().take_while_line(|line| !line.is_empty());
+    /// for line in diamond.line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn take_while_line(mut self, pred: impl FnMut(&str) -> bool + Send + 'static) -> Self {
+        self.hooks.take_while_line = Some(Box::new(pred));
+        self
+    }
+
+    /// Makes the line functions skip the lines at the beginning of each file and standard input
+    /// while `pred` returns `true`, like [`Iterator::skip_while`] applied to each source
+    /// separately.
+    ///
+    /// The predicate is given each line without the line terminator, and the lines from the first
+    /// one for which it returns `false` to the end of the source are returned. This applies to the
+    /// same functions as [`take_while_line`](Self::take_while_line), and the lines skipped are not
+    /// passed to the predicate of `take_while_line`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Skips the leading comments of each file.
+    /// let diamond = diamond_op::new().skip_while_line(|line| line.starts_with('#'));
+    /// for line in diamond.line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn skip_while_line(mut self, pred: impl FnMut(&str) -> bool + Send + 'static) -> Self {
+        self.hooks.skip_while_line = Some(Box::new(pred));
+        self
+    }
+
+    /// Sets the argument that refers to standard input instead of "-".
+    ///
+    /// By default, the token is taken from the `DIAMOND_STDIN_TOKEN` environment variable if set
+    /// when the instance is created, and "-" otherwise. This function takes precedence over the
+    /// environment variable. Once a different token is set, "-" is treated as an ordinary file
+    /// name. The implicit standard input used when no argument is given is unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().stdin_token("STDIN").line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn stdin_token(mut self, token: impl Into<ffi::OsString>) -> Self {
+        self.config.stdin_token = Some(token.into());
+        self
+    }
+
+    /// Makes `/dev/stdin` and `/dev/fd/0` refer to standard input in the same way as "-" on Unix.
+    ///
+    /// By default, these paths are opened as ordinary files, so standard input is not shared with
+    /// "-" and options such as [`buffer_stdin`](Self::buffer_stdin) do not apply to them. With
+    /// this option set, they are read as standard input and reported as given, while
+    /// `/dev/stdout`, `/dev/stderr`, `/dev/fd/1`, and `/dev/fd/2` fail to open with
+    /// [`io::ErrorKind::InvalidInput`] instead of reading from an output stream. This option has no
+    /// effect on the other platforms.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().recognize_dev_std().line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn recognize_dev_std(mut self) -> Self {
+        self.config.recognize_dev_std = true;
+        self
+    }
+
+    /// Sets the sharing mode, a combination of the `FILE_SHARE_*` flags, with which files are
+    /// opened on Windows.
+    ///
+    /// By default, files are opened as by [`fs::File::open`], which shares them for reading,
+    /// writing, and deletion. See
+    /// [`OpenOptionsExt::share_mode`](std::os::windows::fs::OpenOptionsExt::share_mode) for
+    /// details.
+    ///
+    /// # Examples
+    ///
+    /// To prevent other processes from writing to the files while they are open:
+    ///
+    /// ```rust
+    /// const FILE_SHARE_READ: u32 = 0x1;
+    /// for line in diamond_op::new().share_mode(FILE_SHARE_READ).line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(windows)]
+    #[cfg_attr(docsrs, doc(cfg(windows)))]
+    pub fn share_mode(mut self, mode: u32) -> Self {
+        self.config.share_mode = Some(mode);
+        self
+    }
+
+    /// Sets the options with which files are opened.
+    ///
+    /// The options are used in place of [`fs::File::open`], with [`read`](fs::OpenOptions::read)
+    /// always enabled, so that platform-specific flags can be passed through the extension traits
+    /// such as [`OpenOptionsExt`](std::os::unix::fs::OpenOptionsExt). Standard input is not
+    /// affected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut options = std::fs::OpenOptions::new();
+    /// #[cfg(target_os = "linux")]
+    /// std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, 0o400); // O_NOCTTY
+    /// for line in diamond_op::new().open_options(options).line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn open_options(mut self, options: fs::OpenOptions) -> Self {
+        self.config.open_options = Some(options);
+        self
+    }
+
+    /// Retries opening a file up to `attempts` times, waiting `backoff` before each retry, if it
+    /// fails with a transient error.
+    ///
+    /// By default, an error is transient if its kind is [`WouldBlock`](io::ErrorKind::WouldBlock),
+    /// [`TimedOut`](io::ErrorKind::TimedOut), or [`Interrupted`](io::ErrorKind::Interrupted), so
+    /// that other errors such as [`NotFound`](io::ErrorKind::NotFound) fail immediately. Use
+    /// [`open_retry_if`](Self::open_retry_if) to change the classification. Only opening files is
+    /// retried; errors while reading them are not.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// let diamond = diamond_op::new().open_retry(3, Duration::from_millis(100));
+    /// for line in diamond.line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn open_retry(mut self, attempts: usize, backoff: time::Duration) -> Self {
+        self.config.open_retries = attempts;
+        self.config.retry_backoff = backoff;
+        self
+    }
+
+    /// Sets the function that determines whether an error while opening a file is transient and
+    /// thus retried by [`open_retry`](Self::open_retry).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io, time::Duration};
+    /// // also waits for files that are yet to be created
+    /// let diamond = diamond_op::new()
+    ///     .open_retry(10, Duration::from_millis(100))
+    ///     .open_retry_if(|e| e.kind() == io::ErrorKind::NotFound);
+    /// # drop(diamond);
+    /// ```
+    pub fn open_retry_if(mut self, f: fn(&io::Error) -> bool) -> Self {
+        self.config.retry_if = f;
+        self
+    }
+
+    /// Sets the byte that terminates lines, which is `\n` by default, for all the line-oriented
+    /// methods.
+    ///
+    /// The methods that read lines, such as [`read_line`](Self::read_line),
+    /// [`line_iter`](Self::line_iter), [`uniq_iter`](Self::uniq_iter), and
+    /// [`paragraph_iter`](Self::paragraph_iter), split the input at `byte` instead of `\n` and
+    /// strip `byte` instead of `\n` where they compare lines. The lines are still validated as
+    /// UTF-8. The methods that take a delimiter explicitly, such as
+    /// [`read_until`](Self::read_until), are not affected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is not an ASCII character.
+    pub fn line_terminator(mut self, byte: u8) -> Self {
+        assert!(
+            byte.is_ascii(),
+            "line terminator must be an ASCII character"
+        );
+        self.config.line_terminator = byte;
+        self
+    }
+
+    /// Terminates lines with NUL (`\0`) instead of `\n`, like `grep -z` and `sort -z`.
+    ///
+    /// This is a shorthand for [`line_terminator(b'\0')`](Self::line_terminator).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Reads the file names output by `find -print0`.
+    /// for name in diamond_op::new().null_data().line_iter() {
+    ///     let name = name?;
+    ///     println!("{}", name.trim_end_matches('\0'));
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn null_data(self) -> Self {
+        self.line_terminator(b'\0')
+    }
+
+    /// Makes [`read_line`](Self::read_line) and [`read_until`](Self::read_until) behave exactly
+    /// like those of [`BufRead`] over the concatenation of all files and standard input.
+    ///
+    /// By default, these functions also return at the EOF of each file or standard input that does
+    /// not end with the delimiter, so that no line spans files. With this option set, they keep
+    /// reading the next sources instead, so the last line of such a file is joined with the first
+    /// line of the next one, as in `cat a b`. A line without the delimiter is still returned at
+    /// the EOF of all the sources, as `BufRead` does. This is useful as a drop-in replacement of
+    /// the code that reads a single `BufRead`. The other methods are not affected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut diamond = diamond_op::new().strict_bufread_compat();
+    /// let mut buf = String::new();
+    /// while diamond.read_line(&mut buf)? != 0 {
+    ///     print!("{}", buf);
+    ///     buf.clear();
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn strict_bufread_compat(mut self) -> Self {
+        self.config.strict_bufread_compat = true;
+        self
+    }
+
+    /// Sets whether [`paragraph_iter`](Self::paragraph_iter) treats lines that consist only of
+    /// whitespace as blank lines separating paragraphs.
+    ///
+    /// By default, only empty lines separate paragraphs, as in Perl. Setting `yes` helps with
+    /// human-edited files that contain stray spaces on the lines that look blank.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let diamond = diamond_op::new().paragraph_blank_is_whitespace(true);
+    /// for paragraph in diamond.paragraph_iter() {
+    ///     print!("--\n{}", paragraph?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn paragraph_blank_is_whitespace(mut self, yes: bool) -> Self {
+        self.config.paragraph_blank_is_whitespace = yes;
+        self
+    }
+
+    /// Sets whether [`line_batches`](Self::line_batches) fills batches across file boundaries.
+    ///
+    /// By default, each batch holds lines of a single file or standard input.
+    pub fn line_batches_cross_files(mut self, yes: bool) -> Self {
+        self.config.line_batches_cross_files = yes;
+        self
+    }
+
+    /// Stops reading at the next record boundary once `flag` is set, as if all the files had been
+    /// read.
+    ///
+    /// The flag is checked before each record is read by [`read_line`](Self::read_line),
+    /// [`read_until`](Self::read_until), the line iterators, and the like, so that a record is
+    /// never cut in the middle. This allows, e.g., a Ctrl-C handler to set the flag and let the
+    /// processing loop finish its current record and shut down gracefully. Note that the flag is
+    /// shared with the instances built from [`config`](Self::config).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::{Arc, atomic::AtomicBool};
+    /// let interrupted = Arc::new(AtomicBool::new(false));
+    /// // e.g. set `interrupted` from a signal handler
+    /// for line in diamond_op::new().interruptible(Arc::clone(&interrupted)).line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn interruptible(mut self, flag: sync::Arc<sync::atomic::AtomicBool>) -> Self {
+        self.config.interrupt = Some(flag);
+        self
+    }
+
+    /// Registers a function that reads the beginning of the first source, e.g. a header or schema,
+    /// before the other methods read it.
+    ///
+    /// The function is called exactly once with the first source that is opened, and the rest of
+    /// the source that it does not consume is read as usual. An error returned from the function
+    /// is reported as an error reading the source. The bytes consumed by the function count toward
+    /// [`bytes_read`](Self::bytes_read) and the spans and progress derived from it, but not as
+    /// records, so a source consumed entirely is still reported by
+    /// [`empty_sources`](Self::empty_sources).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    /// let header = Arc::new(Mutex::new(String::new()));
+    /// let diamond = diamond_op::new().first_source_callback({
+    ///     let header = Arc::clone(&header);
+    ///     move |reader| reader.read_line(&mut header.lock().unwrap()).map(drop)
+    /// });
+    /// for line in diamond.line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// eprint!("header: {}", header.lock().unwrap());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn first_source_callback(
+        mut self,
+        f: impl FnOnce(&mut dyn BufRead) -> io::Result<()> + Send + 'static,
+    ) -> Self {
+        self.hooks.first_source = Some(Box::new(f));
+        self
+    }
+
+    /// Registers a function that receives a [`Progress`] snapshot as bytes are read.
+    ///
+    /// The function is called each time a record is read, or each time bytes are consumed from
+    /// the reader returned from [`reader`](Self::reader), which is useful to drive a progress bar
+    /// without polling [`bytes_read`](Self::bytes_read) and [`total_bytes`](Self::total_bytes).
+    /// Use [`progress_interval`](Self::progress_interval) to call it less frequently. The totals
+    /// in the snapshot are computed when the first snapshot of each source is taken, so changes to
+    /// the sizes of the files in the middle of a source are not reflected until the next one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let diamond = diamond_op::new().with_progress(|p| {
+    ///     if let Some(total) = p.total_bytes {
+    ///         eprint!("\r{}%", p.bytes_read * 100 / total.max(1));
+    ///     }
+    /// });
+    /// for line in diamond.progress_interval(64 * 1024).line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_progress(mut self, f: impl FnMut(Progress) + Send + 'static) -> Self {
+        self.hooks.on_progress = Some(Box::new(f));
+        self
+    }
+
+    /// Registers a function that is called once when all files and standard input have been read.
+    ///
+    /// The function is called when a read reaches the EOF of the last source and no sources
+    /// remain, before that read returns, and is never called again even if reads continue after
+    /// the EOF. An error returned from the function is returned from that read instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let diamond = diamond_op::new().on_eof(|| {
+    ///     eprintln!("all input consumed");
+    ///     Ok(())
+    /// });
+    /// for line in diamond.line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn on_eof(mut self, f: impl FnOnce() -> io::Result<()> + Send + 'static) -> Self {
+        self.hooks.on_eof = Some(Box::new(f));
+        self
+    }
+
+    /// Calls the function registered by [`with_progress`](Self::with_progress) only after at
+    /// least `bytes` bytes have been read since the last call.
+    ///
+    /// The default is 0, which calls the function for every record.
+    pub fn progress_interval(mut self, bytes: u64) -> Self {
+        self.config.progress_interval = bytes;
+        self
+    }
+
+    /// Limits the number of sources that [`round_robin_iter`](Self::round_robin_iter) keeps open
+    /// at a time to `n`, which is at least 1.
+    ///
+    /// With this option, the rotation starts with the first `n` sources, and each source that
+    /// reaches EOF is closed and replaced with the next one at the end of the rotation, so that a
+    /// large number of files can be read without exhausting file descriptors.
+    /// [`zip_sources`](Self::zip_sources) is not affected because it needs every source at each
+    /// step.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for line in diamond_op::new().max_open_files(64).round_robin_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn max_open_files(mut self, n: usize) -> Self {
+        self.config.max_open_files = Some(n.max(1));
+        self
+    }
+
+    /// Treats the first command line argument as a source rather than skipping it as the program
+    /// name.
+    ///
+    /// This is useful when embedded in an environment where the first element of
+    /// [`env::args_os`] is not the program name. This option must be set before the first line is
+    /// read and has no effect on the instances that do not read command line arguments, e.g.
+    /// those created by [`from_list_file`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// for line in diamond_op::new().keep_first_arg().line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn keep_first_arg(mut self) -> Self {
+        self.config.keep_first_arg = true;
+        self
+    }
+
+    /// Reads the NUL-separated list of sources from the file `source`, or standard input if
+    /// `source` is "-", and uses it instead of the command line arguments, like the
+    /// `--files0-from` option of GNU `wc` and `du`.
+    ///
+    /// This is typically used with the output of `find -print0`, which can safely represent file
+    /// names that contain spaces and newlines. The terminating NUL byte of the last entry is
+    /// optional. Like [`from_list_file`], an empty list does not fall back to standard input.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the list contains an empty entry, i.e. two
+    /// consecutive NUL bytes, or, on non-Unix platforms, an entry that is not valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// // find . -name '*.txt' -print0 | mycmd
+    /// for line in diamond_op::new().files0_from("-")?.line_iter() {
+    ///     print!("{}", line?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn files0_from(mut self, source: impl AsRef<ffi::OsStr>) -> io::Result<Self> {
+        let source = source.as_ref();
+        let mut list = Vec::new();
+        if self.config.is_stdin(source) {
+            io::Read::read_to_end(&mut io::stdin(), &mut list)?;
+        } else {
+            list = fs::read(source).map_err(|e| DiamondError::wrap(ErrorPhase::Open, source, e))?;
+        }
+        if list.last() == Some(&0) {
+            list.pop();
+        }
+
+        let mut args = collections::VecDeque::new();
+        if !list.is_empty() {
+            for entry in list.split(|&b| b == 0) {
+                if entry.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "invalid zero-length file name",
+                    ));
+                }
+                #[cfg(unix)]
+                let entry =
+                    <ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(entry.to_vec());
+                #[cfg(not(unix))]
+                let entry = ffi::OsString::from(
+                    String::from_utf8(entry.to_vec())
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+                );
+                args.push_back(entry);
+            }
+        }
+        self.args = Args(Some(args));
+        Ok(self)
+    }
+
+    fn read_inner(
+        &mut self,
+        mut f: impl FnMut(&mut dyn BufRead) -> io::Result<usize>,
+    ) -> io::Result<usize> {
+        loop {
+            match self.read_record(&mut f)? {
+                Some(0) => continue, // skips the empty source marker
+                Some(ret) => return Ok(ret),
+                None => return Ok(0),
+            }
+        }
+    }
+
+    /// Reads a record from the current or next source, returning `None` at the EOF of all sources
+    /// or `Some(0)` for an empty source if `yield_empty_sources` is set.
+    fn read_record(
+        &mut self,
+        mut f: impl FnMut(&mut dyn BufRead) -> io::Result<usize>,
+    ) -> io::Result<Option<usize>> {
+        if let Some(flag) = &self.config.interrupt {
+            if flag.load(sync::atomic::Ordering::Relaxed) {
+                return Ok(None);
+            }
+        }
+        loop {
+            if let Some(reader) = &mut self.cur_file {
+                if self
+                    .config
+                    .max_lines_per_file
+                    .is_some_and(|n| self.cur_records >= n)
+                {
+                    let has_more = match reader.as_buf_read_mut().fill_buf() {
+                        Ok(buf) => !buf.is_empty(),
+                        Err(e) => {
+                            self.skip_read_error(read_error(&self.cur_arg, e))?;
+                            false
+                        }
+                    };
+                    if has_more {
+                        self.truncated_sources.extend(self.cur_arg.clone());
+                    }
+                    if !self.prepare_next()? {
+                        return Ok(None);
+                    }
+                    continue;
+                }
+                let start = time::Instant::now();
+                let ret = f(reader.as_buf_read_mut());
+                self.metrics.total_read_time += start.elapsed();
+                match ret {
+                    Ok(ret) => {
+                        if ret != 0 || (self.config.yield_empty_sources && self.cur_records == 0) {
+                            self.add_record(ret);
+                            return Ok(Some(ret));
+                        }
+                    }
+                    Err(e) => {
+                        if let Some(len) = InvalidLine::len_of(&e) {
+                            self.count_record(len); // the line has been consumed anyway
+                        }
+                        self.skip_read_error(read_error(&self.cur_arg, e))?
+                    }
+                }
+            }
+            if !self.prepare_next()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Returns `err` unless `skip_read_errors` is set, in which case records `err` so that the
+    /// caller can proceed to the next source.
+    fn skip_read_error(&mut self, err: io::Error) -> io::Result<()> {
+        self.metrics.errors += 1;
+        if self.config.skip_read_errors {
+            self.errors.push(err);
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
+    /// Returns whether `line` passes the per-source line predicates, ending the current source if
+    /// it fails the [`take_while_line`](Self::take_while_line) predicate.
+    fn filter_line(&mut self, line: &str) -> bool {
+        let line = strip_terminator(line, self.config.line_terminator);
+        if self.cur_skipping {
+            if let Some(pred) = &mut self.hooks.skip_while_line {
+                if pred(line) {
+                    self.cur_yielded = self.cur_yielded.saturating_sub(1);
+                    return false;
+                }
+            }
+            self.cur_skipping = false;
+        }
+        if let Some(pred) = &mut self.hooks.take_while_line {
+            if !pred(line) {
+                self.cur_yielded = self.cur_yielded.saturating_sub(1);
+                self.close_current(); // skips the rest of the source
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Counts a record of `n` bytes yielded to the caller.
+    fn add_record(&mut self, n: usize) {
+        self.count_record(n);
+        if n != 0 {
+            self.cur_yielded += 1;
+        }
+    }
+
+    /// Counts a record of `n` bytes consumed from the current source, whether yielded or not.
+    fn count_record(&mut self, n: usize) {
+        self.cur_records += 1;
+        self.metrics.records += 1;
+        self.add_bytes_read(n);
+    }
+
+    /// Counts `n` bytes yielded to the caller outside of records.
+    fn add_bytes_yielded(&mut self, n: usize) {
+        self.add_bytes_read(n);
+        if n != 0 {
+            self.cur_yielded += 1;
+        }
+    }
+
+    fn add_bytes_read(&mut self, n: usize) {
+        self.cur_bytes += n as u64;
+        self.bytes_read += n as u64;
+        if self.hooks.on_progress.is_some()
+            && self.bytes_read - self.progress_reported >= self.config.progress_interval
+        {
+            self.progress_reported = self.bytes_read;
+            let (total_bytes, total_sources) = match self.progress_totals {
+                Some(totals) => totals,
+                None => *self.progress_totals.insert((
+                    self.total_bytes().ok().flatten(),
+                    self.args_taken + self.remaining_sources(),
+                )),
+            };
+            let progress = Progress {
+                bytes_read: self.bytes_read,
+                total_bytes,
+                current_source: self.cur_arg.clone().unwrap_or_default(),
+                source_index: self.args_taken.saturating_sub(1),
+                total_sources: Some(total_sources),
+            };
+            if let Some(f) = &mut self.hooks.on_progress {
+                f(progress);
+            }
+        }
+    }
+
+    /// Appends a record to `buf`, stripping the delimiter `sep`, and returns whether a record has
+    /// been read.
+    fn next_record_in(&mut self, sep: u8, buf: &mut Vec<u8>) -> io::Result<bool> {
+        let len = buf.len();
+        let ret = self.read_record(|reader| {
+            let len = buf.len();
+            reader
+                .read_until(sep, buf)
+                .inspect_err(|_| buf.truncate(len))
+        })?;
+        if ret.is_some() && buf.len() > len && buf.last() == Some(&sep) {
+            buf.pop();
+        }
+        Ok(ret.is_some())
+    }
+
+    /// Closes the current source, if any, recording it as empty if it has yielded nothing and
+    /// keeping its standard input reader for the next source reading standard input.
+    fn close_current(&mut self) {
+        let Some(reader) = self.cur_file.take() else {
+            return;
+        };
+        if self.cur_yielded == 0 {
+            self.empty_sources.extend(self.cur_arg.clone());
+        }
+        if let Some(stdin) = reader.into_stdin() {
+            self.spare_stdin = Some(stdin);
+        }
+    }
+
+    fn prepare_next(&mut self) -> io::Result<bool> {
+        self.close_current();
+        if let Some((arg, start)) = self.cur_offset.take() {
+            self.offsets.insert(arg, start + self.cur_bytes);
+        }
+        self.cur_records = 0;
+        self.cur_bytes = 0;
+        self.cur_yielded = 0;
+        self.cur_skipping = true;
+        self.progress_totals = None;
+        loop {
+            let Some(arg) = self.args.next(&self.config) else {
+                self.cur_arg = None;
+                if let Some(f) = self.hooks.on_eof.take() {
+                    f()?;
+                }
+                if let Some(next) = self.chained.take() {
+                    self.continue_with(*next);
+                    continue;
+                }
+                return Ok(false);
+            };
+            self.args_taken += 1;
+            let reader = self.open_reader(&arg);
+            let key = arg.clone();
+            self.cur_arg = Some(self.hooks.source_name(&self.config, arg));
+            let Some(mut reader) = reader? else {
+                continue; // skipped by the symlink policy
+            };
+            if let Some(start) = reader.position() {
+                // excludes the bytes already consumed by `first_source_callback`
+                self.cur_offset = Some((key, start - self.cur_bytes));
+            }
+            self.cur_file = Some(reader);
+            return Ok(true);
+        }
+    }
+
+    /// Returns an iterator over `self` and the instances chained to it by [`chain`](Self::chain).
+    fn chain_iter(&self) -> impl Iterator<Item = &Self> {
+        iter::successors(Some(self), |d| d.chained.as_deref())
+    }
+
+    /// Returns the number of the sources not opened yet, including those of the chained instances.
+    fn remaining_sources(&self) -> usize {
+        self.chain_iter()
+            .map(|d| d.args.remaining(&d.config).len())
+            .sum()
+    }
+
+    /// Switches to the sources of the chained `next`, keeping the cumulative state of `self`.
+    fn continue_with(&mut self, next: Diamond) {
+        let Diamond {
+            args,
+            stdin_content,
+            named_readers,
+            resume_offsets,
+            chained,
+            config,
+            hooks,
+            ..
+        } = next;
+        self.args = args;
+        self.stdin_content = stdin_content.or(self.stdin_content.take());
+        self.named_readers = named_readers;
+        self.resume_offsets = resume_offsets;
+        self.chained = chained;
+        self.config = config;
+        self.hooks = hooks;
+    }
+
+    /// Returns a reader over the content of standard input, reading it into memory first if it has
+    /// not been read yet.
+    fn replay_stdin(&mut self) -> io::Result<Reader> {
+        let content = match &self.stdin_content {
+            Some(content) => content.clone(),
+            None => {
+                let mut content = Vec::new();
+                let stdin = self.config.stdin_token();
+                if let Some(mut stdin) = Reader::open(stdin, &self.config)? {
+                    io::Read::read_to_end(&mut stdin, &mut content)?;
+                }
+                self.stdin_content.insert(content.into()).clone()
+            }
+        };
+        Ok(Reader::StdinReplay(io::Cursor::new(content)))
+    }
+
+    /// Opens all the remaining sources at once.
+    fn open_all(&mut self) -> io::Result<collections::VecDeque<OpenSource>> {
+        let mut readers = collections::VecDeque::new();
+        self.open_up_to(&mut readers, usize::MAX)?;
+        Ok(readers)
+    }
+
+    /// Opens the remaining sources and appends them to `readers` until it holds `limit` readers.
+    fn open_up_to(
+        &mut self,
+        readers: &mut collections::VecDeque<OpenSource>,
+        limit: usize,
+    ) -> io::Result<()> {
+        while readers.len() < limit {
+            let Some(arg) = self.args.next(&self.config) else {
+                break;
+            };
+            if let Some(reader) = self.open_reader(&arg)? {
+                readers.push_back(OpenSource {
+                    name: self.hooks.source_name(&self.config, arg),
+                    reader,
+                    lines: 0,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn open_reader(&mut self, arg: &ffi::OsStr) -> io::Result<Option<Reader>> {
+        let reader = if let Some(readers) = &mut self.named_readers {
+            Ok(readers.pop_front().map(Reader::Boxed))
+        } else if self.config.is_stdin(arg) && self.config.buffer_stdin {
+            self.replay_stdin().map(Some)
+        } else if self.config.is_stdin(arg) && self.spare_stdin.is_some() {
+            Ok(self.spare_stdin.take())
+        } else {
+            Reader::open(arg, &self.config)
+        };
+        let reader = reader.map_err(|e| {
+            self.metrics.errors += 1;
+            let name = self.hooks.source_name(&self.config, arg.to_owned());
+            DiamondError::wrap(ErrorPhase::Open, &name, e)
+        })?;
+        let Some(mut reader) = reader else {
+            return Ok(None);
+        };
+        self.metrics.files_opened += 1;
+        if let (Some(&offset), Reader::File(r)) = (self.resume_offsets.get(arg), &mut reader) {
+            let name = self.hooks.source_name(&self.config, arg.to_owned());
+            let wrap = |e| DiamondError::wrap(ErrorPhase::Open, &name, e);
+            if offset <= r.get_ref().metadata().map_err(wrap)?.len() {
+                io::Seek::seek(r, io::SeekFrom::Start(offset)).map_err(wrap)?;
+            } else {
+                self.errors.push(wrap(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "saved offset is beyond the end of file; reading from the start",
+                )));
+            }
+        }
+        if let (Some(file), Some(f)) = (reader.file(), &mut self.hooks.on_file_handle) {
+            f(arg, file);
+        }
+        if self.config.stable_read {
+            reader = reader.into_stable();
+        }
+        let mut reader = reader.with_limit(self.config.head_bytes_per_file);
+        if let Some(f) = self.hooks.first_source.take() {
+            let mut counted = CountingReader(reader.as_buf_read_mut(), 0);
+            let ret = f(&mut counted);
+            // counts the bytes consumed as those of the source without yielding a record
+            let n = counted.1 as u64;
+            self.cur_bytes += n;
+            self.bytes_read += n;
+            ret.map_err(|e| {
+                let name = self.hooks.source_name(&self.config, arg.to_owned());
+                DiamondError::wrap(ErrorPhase::Read, &name, e)
+            })?;
+        }
+        Ok(Some(reader))
+    }
+}
+
+/// A planned source, as reported by [`Diamond::plan`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SourcePlan {
+    /// The command line argument of the source, as rewritten by [`Diamond::map_source_name`] if
+    /// set.
+    pub arg: ffi::OsString,
+    /// What the argument refers to.
+    pub resolved: SourceKind,
+    /// The 0-based position of the source in the planned reading order.
+    pub order: usize,
+}
+
+/// The kind of a planned source, as reported by [`Diamond::plan`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SourceKind {
+    /// Standard input.
+    Stdin,
+    /// A regular file, possibly through a symbolic link.
+    File,
+    /// A directory, which fails to be read.
+    Directory,
+    /// Any other file, such as a named pipe or a device, or a reader given to
+    /// [`from_named_readers`].
+    Other,
+    /// A symbolic link, which is rejected by [`SymlinkPolicy::Error`].
+    Symlink,
+    /// A nonexistent file, including the target of a broken symbolic link, which fails to open.
+    Missing,
+}
+
+/// A policy that determines how file arguments that are symbolic links are handled.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SymlinkPolicy {
+    /// Follows symbolic links and reads their targets (default).
+    #[default]
+    Follow,
+    /// Skips symbolic links silently, including broken ones.
+    Skip,
+    /// Fails with an error when a symbolic link is encountered.
+    Error,
+}
+
+/// The location of a line, as reported by [`Diamond::spanned_line_iter`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct RecordSpan {
+    /// The command line argument of the source, as rewritten by [`Diamond::map_source_name`] if
+    /// set.
+    pub source: ffi::OsString,
+    /// The 1-based line number within the source.
+    pub line_number: u64,
+    /// The offset at which the line starts, counting the bytes of all the sources read before.
+    pub start_byte: u64,
+    /// The length of the line in bytes, including the newline byte if any.
+    pub len: usize,
+}
+
+/// Cumulative counters of reading, as returned from [`Diamond::metrics`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Metrics {
+    /// The number of records, such as lines, read so far.
+    pub records: u64,
+    /// The total number of bytes read so far, as returned from [`Diamond::bytes_read`].
+    pub bytes: u64,
+    /// The number of sources opened so far, including standard input.
+    pub files_opened: u64,
+    /// The number of errors that occurred while opening or reading sources, whether skipped or
+    /// returned.
+    pub errors: u64,
+    /// The wall time spent waiting for the sources to fill the buffer and to read records.
+    pub total_read_time: time::Duration,
+}
+
+/// A snapshot of the progress of reading, as reported to [`Diamond::with_progress`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Progress {
+    /// The total number of bytes read so far, as returned from [`Diamond::bytes_read`].
+    pub bytes_read: u64,
+    /// The estimate of the total number of bytes, as returned from [`Diamond::total_bytes`], or
+    /// `None` if unknown.
+    pub total_bytes: Option<u64>,
+    /// The command line argument of the source being read, as rewritten by
+    /// [`Diamond::map_source_name`] if set.
+    pub current_source: ffi::OsString,
+    /// The 0-based position of the source being read, as returned from
+    /// [`Diamond::current_index`].
+    pub source_index: usize,
+    /// The total number of sources, or `None` if unknown.
+    pub total_sources: Option<usize>,
+}
+
+/// A line yielded by [`Diamond::grep_context`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ContextLine {
+    /// The command line argument of the source, as rewritten by [`Diamond::map_source_name`] if
+    /// set.
+    pub source: ffi::OsString,
+    /// The 1-based line number within the source.
+    pub line_number: u64,
+    /// Whether the line is a match rather than a context line.
+    pub is_match: bool,
+    /// The line, including the newline byte if any.
+    pub line: String,
+}
+
+/// Options of [`Diamond::uniq_iter_with`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UniqOptions {
+    /// Compares lines ignoring case, like `uniq -i`.
+    pub ignore_case: bool,
+    /// Compares lines ignoring leading and trailing whitespace.
+    pub ignore_whitespace: bool,
+    /// Prefixes each line with the number of lines in its run, like `uniq -c`.
+    pub count: bool,
+}
+
+impl UniqOptions {
+    /// Returns the key of `line`, stripped of its terminator, to be compared.
+    fn key(&self, key: &str) -> String {
+        let key = if self.ignore_whitespace {
+            key.trim()
+        } else {
+            key
+        };
+        if self.ignore_case {
+            key.to_lowercase()
+        } else {
+            key.to_owned()
+        }
+    }
+
+    fn prefix_count((line, n): (String, u64)) -> String {
+        format!("{:7} {}", n, line)
+    }
+}
+
+/// Counts of the line terminators found in a source, as reported by
+/// [`Diamond::line_ending_report`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct LineEndingStats {
+    /// The number of lines terminated by `\n` not preceded by `\r`.
+    pub lf: u64,
+    /// The number of lines terminated by `\r\n`.
+    pub crlf: u64,
+    /// The number of lines terminated by `\r` not followed by `\n`.
+    pub cr: u64,
+    /// The number of final lines without a terminator, which is 0 or 1.
+    pub unterminated: u64,
+}
+
+impl LineEndingStats {
+    fn count(reader: &mut dyn BufRead) -> io::Result<Self> {
+        let mut stats = Self::default();
+        let mut pending_cr = false;
+        let mut terminated = true; // whether the last byte ends a line
+        loop {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            for &b in buf {
+                if pending_cr {
+                    pending_cr = false;
+                    if b == b'\n' {
+                        stats.crlf += 1;
+                        continue;
+                    }
+                    stats.cr += 1;
+                }
+                match b {
+                    b'\r' => pending_cr = true,
+                    b'\n' => stats.lf += 1,
+                    _ => {}
+                }
+                terminated = matches!(b, b'\r' | b'\n');
+            }
+            let len = buf.len();
+            reader.consume(len);
+        }
+        if pending_cr {
+            stats.cr += 1;
+        }
+        if !terminated {
+            stats.unterminated += 1;
+        }
+        Ok(stats)
+    }
+}
+
+/// An error that occurred while opening or reading a source.
+///
+/// Errors returned by `Diamond` and its iterators wrap this type in an [`io::Error`] of the same
+/// [`kind`](io::Error::kind) as the original error, so that callers can tell which source failed
+/// and whether it failed to open or to read. Use [`DiamondError::get`] to extract it.
+///
+/// # Examples
+///
+/// ```rust
+/// use diamond_op::{DiamondError, ErrorPhase};
+///
+/// for line in diamond_op::new().line_iter() {
+///     match line {
+///         Ok(line) => print!("{}", line),
+///         Err(e) => match DiamondError::get(&e) {
+///             Some(err) if err.phase() == ErrorPhase::Open => eprintln!("skipped: {}", err),
+///             _ => return Err(e),
+///         },
+///     }
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct DiamondError {
+    phase: ErrorPhase,
+    arg: ffi::OsString,
+    source: io::Error,
+}
+
+impl DiamondError {
+    /// Returns the `DiamondError` wrapped in `err`, if any.
+    pub fn get(err: &io::Error) -> Option<&Self> {
+        err.get_ref()?.downcast_ref()
+    }
+
+    /// Returns the stage at which the error occurred.
+    pub fn phase(&self) -> ErrorPhase {
+        self.phase
+    }
+
+    /// Returns the command line argument of the source that caused the error, as rewritten by
+    /// [`Diamond::map_source_name`] if set.
+    pub fn arg(&self) -> &ffi::OsStr {
+        &self.arg
+    }
+
+    /// Returns the original error.
+    pub fn io_error(&self) -> &io::Error {
+        &self.source
+    }
+
+    /// Returns the conventional process exit status for a program that stops on `err`.
+    ///
+    /// The status follows the conventions of `grep` and `diff`, which tell errors with the input
+    /// files from other failures:
+    ///
+    /// - 0 for [`io::ErrorKind::BrokenPipe`], as the reader of the output has exited and nothing
+    ///   is left to do; print no message in this case.
+    /// - 2 for [`io::ErrorKind::NotFound`] and [`io::ErrorKind::PermissionDenied`], which usually
+    ///   indicate a wrong command line argument.
+    /// - 1 for the other errors.
+    ///
+    /// `err` need not wrap a `DiamondError`. Match on [`io::Error::kind`] directly to use other
+    /// conventions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use diamond_op::DiamondError;
+    ///
+    /// fn run() -> std::io::Result<()> {
+    ///     for line in diamond_op::new().line_iter() {
+    ///         print!("{}", line?);
+    ///     }
+    ///     Ok(())
+    /// }
+    ///
+    /// if let Err(e) = run() {
+    ///     let code = DiamondError::exit_code_for(&e);
+    ///     if code != 0 {
+    ///         eprintln!("error: {}", e);
+    ///     }
+    ///     std::process::exit(code);
+    /// }
+    /// ```
+    pub fn exit_code_for(err: &io::Error) -> i32 {
+        match err.kind() {
+            io::ErrorKind::BrokenPipe => 0,
+            io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => 2,
+            _ => 1,
+        }
+    }
+
+    fn wrap(phase: ErrorPhase, arg: &ffi::OsStr, source: io::Error) -> io::Error {
+        if Self::get(&source).is_some() {
+            return source;
+        }
+        let kind = source.kind();
+        let arg = arg.to_owned();
+        io::Error::new(kind, Self { phase, arg, source })
+    }
+}
+
+impl fmt::Display for DiamondError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.arg.to_string_lossy(), self.source)
+    }
+}
+
+impl error::Error for DiamondError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// An error yielded by [`Diamond::try_line_iter`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LineError {
+    /// A line that is not valid UTF-8, after which the iteration continues.
+    InvalidUtf8 {
+        /// The command line argument of the source, as rewritten by [`Diamond::map_source_name`]
+        /// if set.
+        arg: ffi::OsString,
+        /// The 1-based line number within the source.
+        line: u64,
+        /// The raw bytes of the line, including the newline byte if any.
+        bytes: Vec<u8>,
+    },
+    /// An I/O error, after which the iteration ends.
+    Io(io::Error),
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUtf8 { arg, line, .. } => {
+                write!(
+                    f,
+                    "{}:{}: stream did not contain valid UTF-8",
+                    arg.to_string_lossy(),
+                    line
+                )
+            }
+            Self::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for LineError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::InvalidUtf8 { .. } => None,
+            Self::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<LineError> for io::Error {
+    fn from(err: LineError) -> Self {
+        match err {
+            LineError::InvalidUtf8 { .. } => io::Error::new(io::ErrorKind::InvalidData, err),
+            LineError::Io(e) => e,
+        }
+    }
+}
+
+/// The stage at which a [`DiamondError`] occurred.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorPhase {
+    /// Failed to open a source, e.g. because the file does not exist.
+    Open,
+    /// Failed to read from a source that has been opened.
+    Read,
+}
+
+/// Wraps an error that occurred while reading the current source `arg`.
+fn read_error(arg: &Option<ffi::OsString>, err: io::Error) -> io::Error {
+    DiamondError::wrap(ErrorPhase::Read, arg.as_deref().unwrap_or_default(), err)
+}
+
+/// Reads the rest of `reader`, tagging an error with [`ErrorPhase::Read`].
+fn read_to_end(reader: &mut Reader) -> Result<Vec<u8>, (ErrorPhase, io::Error)> {
+    let mut content = Vec::new();
+    match io::Read::read_to_end(reader, &mut content) {
+        Ok(_) => Ok(content),
+        Err(e) => Err((ErrorPhase::Read, e)),
+    }
+}
+
+/// Reads the whole file `arg` on a worker thread of [`Diamond::par_files_ordered`], returning
+/// `None` if the file is skipped by the symlink policy.
+fn read_file_to_end(
+    arg: &ffi::OsStr,
+    config: &DiamondConfig,
+) -> Option<Result<Vec<u8>, (ErrorPhase, io::Error)>> {
+    match Reader::open(arg, config) {
+        Ok(Some(reader)) => Some(read_to_end(
+            &mut reader.with_limit(config.head_bytes_per_file),
+        )),
+        Ok(None) => None,
+        Err(e) => Some(Err((ErrorPhase::Open, e))),
+    }
+}
+
+/// Options that control how a [`Diamond`] reads its sources.
+///
+/// A configuration is obtained from a configured instance by [`Diamond::config`] and builds other
+/// instances with the same options for different sets of arguments. It covers all the options set
+/// by the builder methods of `Diamond` except the callbacks, i.e.
+/// [`on_file_handle`](Diamond::on_file_handle), [`map_source_name`](Diamond::map_source_name),
+/// [`first_source_callback`](Diamond::first_source_callback),
+/// [`with_progress`](Diamond::with_progress), [`on_eof`](Diamond::on_eof),
+/// [`take_while_line`](Diamond::take_while_line), and
+/// [`skip_while_line`](Diamond::skip_while_line), the offsets given to
+/// [`resume_from`](Diamond::resume_from), and none of the reading state.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// let config = diamond_op::new().skip_read_errors().stdin_capacity(512).config();
+/// for batch in [["a.txt", "b.txt"], ["c.txt", "d.txt"]] {
+///     for line in config.build_with_args(batch).line_iter() {
+///         print!("{}", line?);
+///     }
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct DiamondConfig {
+    yield_empty_sources: bool,
+    symlinks: SymlinkPolicy,
+    stdin_timeout: Option<time::Duration>,
+    skip_read_errors: bool,
+    stdin_capacity: Option<usize>,
+    head_bytes_per_file: Option<u64>,
+    buffer_stdin: bool,
+    stdin_token: Option<ffi::OsString>,
+    #[cfg(windows)]
+    share_mode: Option<u32>,
+    open_options: Option<fs::OpenOptions>,
+    open_retries: usize,
+    retry_backoff: time::Duration,
+    retry_if: fn(&io::Error) -> bool,
+    keep_first_arg: bool,
+    max_open_files: Option<usize>,
+    interrupt: Option<sync::Arc<sync::atomic::AtomicBool>>,
+    paragraph_blank_is_whitespace: bool,
+    progress_interval: u64,
+    line_terminator: u8,
+    line_batches_cross_files: bool,
+    strict_bufread_compat: bool,
+    canonicalize_names: bool,
+    recognize_dev_std: bool,
+    max_lines_per_file: Option<u64>,
+    stable_read: bool,
+}
+
+impl Default for DiamondConfig {
+    fn default() -> Self {
+        Self {
+            yield_empty_sources: false,
+            symlinks: SymlinkPolicy::default(),
+            stdin_timeout: None,
+            skip_read_errors: false,
+            stdin_capacity: None,
+            head_bytes_per_file: None,
+            buffer_stdin: false,
+            stdin_token: env::var_os(STDIN_TOKEN_VAR),
+            #[cfg(windows)]
+            share_mode: None,
+            open_options: None,
+            open_retries: 0,
+            retry_backoff: time::Duration::ZERO,
+            retry_if: is_transient,
+            keep_first_arg: false,
+            max_open_files: None,
+            interrupt: None,
+            paragraph_blank_is_whitespace: false,
+            progress_interval: 0,
+            line_terminator: b'\n',
+            line_batches_cross_files: false,
+            strict_bufread_compat: false,
+            canonicalize_names: false,
+            recognize_dev_std: false,
+            max_lines_per_file: None,
+            stable_read: false,
+        }
+    }
+}
+
+impl DiamondConfig {
+    /// Returns a diamond operator instance that reads the sources specified by `args` with this
+    /// configuration.
+    ///
+    /// Unlike command line arguments, `args` does not include the program name, and an empty
+    /// `args` does not fall back to standard input.
+    pub fn build_with_args<I>(&self, args: I) -> Diamond
+    where
+        I: IntoIterator,
+        I::Item: Into<ffi::OsString>,
+    {
+        Diamond {
+            args: Args(Some(args.into_iter().map(Into::into).collect())),
+            config: self.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns a diamond operator instance that reads the sources specified by `args_os` as if
+    /// they were the command line arguments.
+    ///
+    /// Like [`env::args_os`], `args_os` includes the program name, which is skipped unless
+    /// [`keep_first_arg`](Diamond::keep_first_arg) is set, and the instance falls back to standard
+    /// input if no argument remains, even if `args_os` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let config = diamond_op::DiamondConfig::default();
+    /// let diamond = config.build_with_args_os(["mycmd", "a.txt"]);
+    /// assert_eq!(diamond.source_names(), ["a.txt"]);
+    /// let diamond = config.build_with_args_os(Vec::<std::ffi::OsString>::new());
+    /// assert_eq!(diamond.source_names(), ["-"]);
+    /// ```
+    pub fn build_with_args_os<I>(&self, args_os: I) -> Diamond
+    where
+        I: IntoIterator,
+        I::Item: Into<ffi::OsString>,
+    {
+        let args_os = args_os.into_iter().map(Into::into);
+        Diamond {
+            args: Args(Some(Args::with_fallback(args_os, self))),
+            config: self.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the argument that refers to standard input.
+    fn stdin_token(&self) -> &ffi::OsStr {
+        self.stdin_token.as_deref().unwrap_or("-".as_ref())
+    }
+
+    /// Returns whether `arg` refers to standard input.
+    fn is_stdin(&self, arg: &ffi::OsStr) -> bool {
+        arg == self.stdin_token()
+            || (self.recognize_dev_std && cfg!(unix) && DEV_STD_INPUTS.iter().any(|&p| arg == p))
+    }
+}
+
+/// Returns whether opening a file may succeed if retried after failing with `err`.
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted
+    )
+}
+
+/// The environment variable that overrides the argument referring to standard input.
+const STDIN_TOKEN_VAR: &str = "DIAMOND_STDIN_TOKEN";
+
+/// The device paths recognized as standard input by [`Diamond::recognize_dev_std`].
+const DEV_STD_INPUTS: [&str; 2] = ["/dev/stdin", "/dev/fd/0"];
+
+/// The device paths rejected as inputs by [`Diamond::recognize_dev_std`].
+const DEV_STD_OUTPUTS: [&str; 4] = ["/dev/stdout", "/dev/stderr", "/dev/fd/1", "/dev/fd/2"];
+
+/// User-supplied callbacks invoked while reading.
+#[derive(Default)]
+struct Hooks {
+    on_file_handle: Option<FileHandleHook>,
+    map_source_name: Option<SourceNameMap>,
+    first_source: Option<FirstSourceHook>,
+    on_progress: Option<ProgressHook>,
+    on_eof: Option<EofHook>,
+    take_while_line: Option<LinePredicate>,
+    skip_while_line: Option<LinePredicate>,
+}
+
+impl Hooks {
+    /// Returns the name of `arg` to be reported to the user.
+    fn source_name(&self, config: &DiamondConfig, arg: ffi::OsString) -> ffi::OsString {
+        let arg = if config.canonicalize_names && !config.is_stdin(&arg) {
+            fs::canonicalize(&arg).map_or(arg, Into::into)
+        } else {
+            arg
+        };
+        match &self.map_source_name {
+            Some(f) => f(&arg),
+            None => arg,
+        }
+    }
+}
+
+type FileHandleHook = Box<dyn FnMut(&ffi::OsStr, &fs::File) + Send>;
+type SourceNameMap = Box<dyn Fn(&ffi::OsStr) -> ffi::OsString + Send>;
+type FirstSourceHook = Box<dyn FnOnce(&mut dyn BufRead) -> io::Result<()> + Send>;
+type ProgressHook = Box<dyn FnMut(Progress) + Send>;
+type EofHook = Box<dyn FnOnce() -> io::Result<()> + Send>;
+type LinePredicate = Box<dyn FnMut(&str) -> bool + Send>;
+type BoxedReader = Box<dyn BufRead + Send>;
+type BoundaryCallback = Box<dyn FnMut(&ffi::OsStr)>;
+
+/// A command line argument queue that falls back to standard input if no argument is given.
+#[derive(Debug, Default)]
+struct Args(Option<collections::VecDeque<ffi::OsString>>);
+
+impl Args {
+    /// Returns the elements of `base` after skipping the first one as the program name unless
+    /// `keep_first_arg` is set, or a single standard input token if no element remains.
+    ///
+    /// An empty `base`, which lacks even the program name, also falls back to standard input.
+    fn with_fallback(
+        base: impl IntoIterator<Item = ffi::OsString>,
+        config: &DiamondConfig,
+    ) -> collections::VecDeque<ffi::OsString> {
+        let skip = if config.keep_first_arg { 0 } else { 1 };
+        let mut args = base
+            .into_iter()
+            .skip(skip)
+            .collect::<collections::VecDeque<_>>();
+        if args.is_empty() {
+            args.push_back(config.stdin_token().to_owned());
+        }
+        args
+    }
+
+    /// Returns the arguments that have not been taken yet without consuming them.
+    fn remaining(&self, config: &DiamondConfig) -> Vec<ffi::OsString> {
+        match &self.0 {
+            Some(args) => args.iter().cloned().collect(),
+            None => Self::with_fallback(env::args_os(), config).into(),
+        }
+    }
+
+    /// Returns whether all the arguments have been taken.
+    fn is_empty(&self) -> bool {
+        // unread command line arguments include at least the fallback
+        self.0.as_ref().is_some_and(collections::VecDeque::is_empty)
+    }
+
+    /// Takes the next argument, reading the command line arguments first if not yet read.
+    fn next(&mut self, config: &DiamondConfig) -> Option<ffi::OsString> {
+        self.0
+            .get_or_insert_with(|| Self::with_fallback(env::args_os(), config))
+            .pop_front()
+    }
+}
+
+/// Reads a line terminated by `term` or EOF into `buf`, failing if it is not valid UTF-8.
+///
+/// Unlike [`BufRead::read_line`], this function guarantees that `buf` is left unchanged on error
+/// and that the whole line has been consumed if it is not valid UTF-8.
+fn read_line(reader: &mut dyn BufRead, term: u8, buf: &mut String) -> io::Result<usize> {
+    let len = buf.len();
+    // SAFETY: the bytes appended are validated or truncated before `buf` is used as a `String`.
+    let bytes = unsafe { buf.as_mut_vec() };
+    let ret = reader
+        .read_until(term, bytes)
+        .and_then(|n| match str::from_utf8(&bytes[len..]) {
+            Ok(_) => Ok(n),
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                InvalidLine { len: n },
+            )),
+        });
+    if ret.is_err() {
+        bytes.truncate(len);
+    }
+    ret
+}
+
+/// The error of a line consumed by [`read_line`] that is not valid UTF-8, which carries the length
+/// of the line so that the line is still counted as read.
+#[derive(Debug)]
+struct InvalidLine {
+    len: usize,
+}
+
+impl InvalidLine {
+    /// Returns the length of the line if `err` is an `InvalidLine`.
+    fn len_of(err: &io::Error) -> Option<usize> {
+        Some(err.get_ref()?.downcast_ref::<Self>()?.len)
+    }
+}
+
+impl fmt::Display for InvalidLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("stream did not contain valid UTF-8")
+    }
+}
+
+impl error::Error for InvalidLine {}
+
+/// Strips the line terminator `term`, which is an ASCII byte, from the end of `line`.
+fn strip_terminator(line: &str, term: u8) -> &str {
+    line.strip_suffix(char::from(term)).unwrap_or(line)
+}
+
+/// Reads all bytes into `buf` until a delimiter byte marked in `table` or EOF is reached.
+fn read_until_any(
+    reader: &mut dyn BufRead,
+    table: &[bool; 256],
+    buf: &mut Vec<u8>,
+) -> io::Result<(usize, Option<u8>)> {
+    let mut read = 0;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if available.is_empty() {
+            return Ok((read, None));
+        }
+        match available.iter().position(|&b| table[usize::from(b)]) {
+            Some(i) => {
+                let delim = available[i];
+                buf.extend_from_slice(&available[..=i]);
+                reader.consume(i + 1);
+                return Ok((read + i + 1, Some(delim)));
+            }
+            None => {
+                let n = available.len();
+                buf.extend_from_slice(available);
+                reader.consume(n);
+                read += n;
+            }
+        }
+    }
+}
+
+/// Counts the non-overlapping occurrences of `needle` in the rest of `reader`.
+fn count_matches(reader: &mut dyn BufRead, needle: &[u8]) -> io::Result<u64> {
+    check_needle(needle)?;
+    let mut count = 0;
+    let mut window = Vec::new();
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(count);
+        }
+        window.extend_from_slice(buf);
+        let n = buf.len();
+        reader.consume(n);
+
+        let mut pos = 0;
+        while pos + needle.len() <= window.len() {
+            if window[pos..].starts_with(needle) {
+                count += 1;
+                pos += needle.len();
+            } else {
+                pos += 1;
+            }
+        }
+        // keeps the tail that may be the beginning of an occurrence
+        window.drain(..pos.max((window.len() + 1).saturating_sub(needle.len())));
+    }
+}
+
+/// Rejects an empty needle, which would match everywhere.
+fn check_needle(needle: &[u8]) -> io::Result<()> {
+    if needle.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty needle"));
+    }
+    Ok(())
+}
+
+/// Reads the next line of the `index`-th source for [`Diamond::merge_sorted`].
+fn next_merge_line<F>(
+    source: &mut OpenSource,
+    index: usize,
+    config: &DiamondConfig,
+    cmp: &rc::Rc<F>,
+) -> io::Result<Option<MergeLine<F>>> {
+    let mut line = String::new();
+    Ok(match source.read_line(config, &mut line)? {
+        0 => None,
+        _ => Some(MergeLine {
+            key_len: strip_terminator(&line, config.line_terminator).len(),
+            line,
+            index,
+            cmp: rc::Rc::clone(cmp),
+        }),
+    })
+}
+
+/// A source kept open alongside the others by the functions reading several sources at once.
+struct OpenSource {
+    name: ffi::OsString,
+    reader: Reader,
+    /// The number of lines read from the source, counted to apply `max_lines_per_file`.
+    lines: u64,
+}
+
+impl OpenSource {
+    /// Reads a line into `buf` like [`read_line`], returning `Ok(0)` once `max_lines_per_file`
+    /// lines have been read, and wraps an error with the name of the source.
+    fn read_line(&mut self, config: &DiamondConfig, buf: &mut String) -> io::Result<usize> {
+        if config.max_lines_per_file.is_some_and(|n| self.lines >= n) {
+            return Ok(0);
+        }
+        let ret = read_line(self.reader.as_buf_read_mut(), config.line_terminator, buf);
+        // counts a line that is not valid UTF-8 as well, as it has been consumed
+        if ret
+            .as_ref()
+            .map_or_else(|e| InvalidLine::len_of(e).is_some(), |&n| n != 0)
+        {
+            self.lines += 1;
+        }
+        ret.map_err(|e| DiamondError::wrap(ErrorPhase::Read, &self.name, e))
+    }
+}
+
+/// A line held by [`Diamond::merge_sorted`], ordered so that [`collections::BinaryHeap`] pops the
+/// least line of the earliest source first.
+struct MergeLine<F> {
+    line: String,
+    key_len: usize,
+    index: usize,
+    cmp: rc::Rc<F>,
+}
+
+impl<F: Fn(&str, &str) -> cmp::Ordering> Ord for MergeLine<F> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        let (key, other_key) = (&self.line[..self.key_len], &other.line[..other.key_len]);
+        (self.cmp)(other_key, key).then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl<F: Fn(&str, &str) -> cmp::Ordering> PartialOrd for MergeLine<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: Fn(&str, &str) -> cmp::Ordering> PartialEq for MergeLine<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<F: Fn(&str, &str) -> cmp::Ordering> Eq for MergeLine<F> {}
+
+/// A reader that reads the lines of a source from the last to the first.
+struct ReverseLines {
+    /// The regular file to read backwards, or `None` if the whole source is in `buf`.
+    file: Option<fs::File>,
+    /// The offset of the file at which the source starts.
+    start: u64,
+    /// The offset of the file up to which the bytes have been read into `buf`.
+    pos: u64,
+    /// The bytes read but not yielded yet.
+    buf: Vec<u8>,
+    term: u8,
+}
+
+impl ReverseLines {
+    const BLOCK_SIZE: u64 = 64 * 1024;
+
+    fn new(reader: Reader, term: u8) -> io::Result<Self> {
+        let mut lines = Self {
+            file: None,
+            start: 0,
+            pos: 0,
+            buf: Vec::new(),
+            term,
+        };
+        match reader {
+            Reader::File(mut r) if r.get_ref().metadata()?.is_file() => {
+                lines.start = io::Seek::stream_position(&mut r)?;
+                let mut file = r.into_inner();
+                lines.pos = io::Seek::seek(&mut file, io::SeekFrom::End(0))?.max(lines.start);
+                lines.file = Some(file);
+            }
+            mut reader => {
+                io::Read::read_to_end(&mut reader, &mut lines.buf)?;
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Returns the last line not yielded yet, reading a block before it if necessary.
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            // excludes the terminator of the last line itself
+            let body = &self.buf[..self.buf.len().saturating_sub(1)];
+            if let Some(i) = body.iter().rposition(|&b| b == self.term) {
+                return Ok(Some(self.buf.split_off(i + 1)));
+            }
+            let Some(file) = self.file.as_mut().filter(|_| self.pos > self.start) else {
+                return Ok((!self.buf.is_empty()).then(|| mem::take(&mut self.buf)));
+            };
+            let n = Self::BLOCK_SIZE.min(self.pos - self.start);
+            self.pos -= n;
+            let mut block = vec![0; n as usize];
+            io::Seek::seek(file, io::SeekFrom::Start(self.pos))?;
+            io::Read::read_exact(file, &mut block)?;
+            block.append(&mut self.buf);
+            self.buf = block;
+        }
+    }
+}
+
+/// A single stream reader that copies the consumed bytes to a writer.
+struct TeeReader<W> {
+    inner: SingleStreamReader,
+    sink: W,
+    /// An error writing to `sink`, deferred to the next read because `consume` cannot fail.
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> io::Read for TeeReader<W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.fill_buf()?.read(buf)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<W: io::Write> BufRead for TeeReader<W> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        let buf = self.inner.fill_buf()?;
+        if buf.is_empty() {
+            self.sink.flush()?;
+        }
+        Ok(buf)
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if amount > 0 && self.error.is_none() {
+            // returns the buffered bytes without reading any more
+            let ret = self.inner.fill_buf();
+            if let Err(e) = ret.and_then(|buf| self.sink.write_all(&buf[..amount])) {
+                self.error = Some(e);
+            }
+        }
+        self.inner.consume(amount);
+    }
+}
+
+/// A reader that reads all sources of a [`Diamond`] as a single stream.
+struct SingleStreamReader {
+    diamond: Diamond,
+    sep: Vec<u8>,
+    /// The number of bytes of `sep` consumed; `sep` is pending while this is less than its length.
+    sep_pos: usize,
+    /// A callback invoked with the name of each source when the reader advances to it.
+    on_boundary: Option<BoundaryCallback>,
+    /// An error that occurred after some bytes had been read, deferred to the next call.
+    pending_error: Option<io::Error>,
+}
+
+impl SingleStreamReader {
+    fn new(diamond: Diamond, sep: Vec<u8>) -> Self {
+        let sep_pos = sep.len();
+        Self {
+            diamond,
+            sep,
+            sep_pos,
+            on_boundary: None,
+            pending_error: None,
+        }
+    }
+}
+
+/// Fills `buf` as much as possible across sources, returning a short read only at the EOF of all
+/// sources or when an error occurs after some bytes have been read, in which case the error is
+/// returned by the next call.
+impl io::Read for SingleStreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() {
+            let ret = self.fill_buf().and_then(|mut b| b.read(&mut buf[n..]));
+            match ret {
+                Ok(0) => break,
+                Ok(len) => {
+                    self.consume(len);
+                    n += len;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if n > 0 => {
+                    self.pending_error = Some(e);
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(n)
+    }
+}
+
+impl BufRead for SingleStreamReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+        loop {
+            if self.sep_pos < self.sep.len() {
+                return Ok(&self.sep[self.sep_pos..]);
+            }
+            let diamond = &mut self.diamond;
+            if let Some(reader) = &mut diamond.cur_file {
+                let start = time::Instant::now();
+                let ret = reader.as_buf_read_mut().fill_buf();
+                diamond.metrics.total_read_time += start.elapsed();
+                match ret.map(|ret| (ret.as_ptr(), ret.len())) {
+                    Ok((_, 0)) => {}
+                    // Intends to `return Ok(ret);` but hacks the borrow checker to work around the
+                    // "conditional returns" limitation:
+                    // https://github.com/rust-lang/rust/issues/51545
+                    Ok((ptr, len)) => return Ok(unsafe { slice::from_raw_parts(ptr, len) }),
+                    Err(e) => diamond.skip_read_error(read_error(&diamond.cur_arg, e))?,
+                }
+            }
+            let is_first = diamond.cur_arg.is_none();
+            if !diamond.prepare_next()? {
+                return Ok(&[]);
+            }
+            if let (Some(f), Some(arg)) = (&mut self.on_boundary, &diamond.cur_arg) {
+                f(arg);
+            }
+            if !is_first {
+                self.sep_pos = 0;
+            }
+        }
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if self.sep_pos < self.sep.len() {
+            self.sep_pos += amount;
+        } else if let Some(reader) = &mut self.diamond.cur_file {
+            reader.as_buf_read_mut().consume(amount);
+            self.diamond.add_bytes_yielded(amount);
+        }
+    }
+}
+
 #[non_exhaustive]
 enum Reader {
-    Stdin(io::StdinLock<'static>),
+    Stdin(io::BufReader<io::Stdin>),
+    TimedStdin(TimedStdin),
     File(io::BufReader<fs::File>),
+    StdinReplay(io::Cursor<sync::Arc<[u8]>>),
+    Limited(io::Take<Box<Reader>>),
+    Boxed(BoxedReader),
+    Stable(StableFile),
 }
 
 impl Reader {
-    fn open(arg: &ffi::OsStr) -> io::Result<Self> {
-        if arg == "-" {
-            Ok(Self::Stdin(io::stdin().lock()))
-        } else {
-            let file = fs::File::open(arg)?;
-            Ok(Self::File(io::BufReader::new(file)))
+    /// Opens the source specified by `arg`, returning `None` if it is to be skipped.
+    fn open(arg: &ffi::OsStr, config: &DiamondConfig) -> io::Result<Option<Self>> {
+        if config.is_stdin(arg) {
+            return Ok(Some(match (config.stdin_timeout, config.stdin_capacity) {
+                (Some(timeout), cap) => Self::TimedStdin(TimedStdin::spawn(timeout, cap)),
+                (None, Some(cap)) => Self::Stdin(io::BufReader::with_capacity(cap, io::stdin())),
+                (None, None) => Self::Stdin(io::BufReader::new(io::stdin())),
+            }));
+        }
+        if config.recognize_dev_std && cfg!(unix) && DEV_STD_OUTPUTS.iter().any(|&p| arg == p) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "refers to standard output or standard error",
+            ));
+        }
+
+        if config.symlinks != SymlinkPolicy::Follow {
+            // falls through to `File::open` to report nonexistent files as usual
+            if fs::symlink_metadata(arg).is_ok_and(|m| m.file_type().is_symlink()) {
+                if config.symlinks == SymlinkPolicy::Skip {
+                    return Ok(None);
+                }
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "is a symbolic link",
+                ));
+            }
+        }
+
+        let mut options = config
+            .open_options
+            .clone()
+            .unwrap_or_else(fs::OpenOptions::new);
+        options.read(true);
+        #[cfg(windows)]
+        if let Some(mode) = config.share_mode {
+            use std::os::windows::fs::OpenOptionsExt as _;
+            options.share_mode(mode);
+        }
+        let mut retries = 0;
+        let file = loop {
+            match options.open(arg) {
+                Err(e) if retries < config.open_retries && (config.retry_if)(&e) => {
+                    retries += 1;
+                    thread::sleep(config.retry_backoff);
+                }
+                ret => break ret?,
+            }
+        };
+        Ok(Some(Self::File(io::BufReader::new(file))))
+    }
+
+    /// Returns the standard input reader underlying `self`, unless it is not one or has reached EOF
+    /// for good.
+    fn into_stdin(self) -> Option<Self> {
+        match self {
+            Self::Stdin(_) => Some(self),
+            Self::TimedStdin(r) if !r.eof => Some(Self::TimedStdin(r)),
+            Self::Limited(r) => r.into_inner().into_stdin(),
+            _ => None,
         }
     }
 
     fn as_buf_read_mut(&mut self) -> &mut dyn BufRead {
         match self {
             Self::Stdin(r) => r,
+            Self::TimedStdin(r) => r,
             Self::File(r) => r,
+            Self::StdinReplay(r) => r,
+            Self::Boxed(r) => r,
+            Self::Limited(r) => r,
+            Self::Stable(r) => r,
+        }
+    }
+
+    /// Returns the offset of the file from which the next byte is read, or `None` if the source is
+    /// not a seekable file.
+    fn position(&mut self) -> Option<u64> {
+        match self {
+            Self::File(r) => io::Seek::stream_position(r).ok(),
+            Self::Limited(r) => r.get_mut().position(),
+            Self::Stable(r) => io::Seek::stream_position(&mut r.0).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes buffered but not consumed yet, without reading more.
+    fn buffer(&self) -> &[u8] {
+        match self {
+            Self::Stdin(r) => r.buffer(),
+            Self::TimedStdin(r) => &r.buf[r.pos..],
+            Self::File(r) => r.buffer(),
+            Self::Stable(r) => r.0.buffer(),
+            Self::StdinReplay(r) => {
+                let content = r.get_ref();
+                &content[(r.position() as usize).min(content.len())..]
+            }
+            Self::Limited(r) => {
+                let buf = r.get_ref().buffer();
+                &buf[..buf.len().min(r.limit() as usize)]
+            }
+            Self::Boxed(_) => &[], // not accessible without reading more
+        }
+    }
+
+    /// Wraps the reader to stop at `limit` bytes if `limit` is given.
+    fn with_limit(self, limit: Option<u64>) -> Self {
+        match limit {
+            Some(limit) => Self::Limited(io::Read::take(Box::new(self), limit)),
+            None => self,
+        }
+    }
+
+    /// Makes a regular file pick up the data appended to it before reporting EOF; other readers
+    /// are returned as is.
+    fn into_stable(self) -> Self {
+        match self {
+            Self::File(r) if r.get_ref().metadata().is_ok_and(|m| m.is_file()) => {
+                Self::Stable(StableFile(r))
+            }
+            other => other,
+        }
+    }
+
+    /// Seeks to the beginning of the source, resetting the byte limit of `Limited` to `limit`.
+    fn rewind(&mut self, limit: Option<u64>) -> io::Result<()> {
+        match self {
+            Self::File(r) => io::Seek::rewind(r),
+            Self::Stable(r) => io::Seek::rewind(&mut r.0),
+            Self::StdinReplay(r) => {
+                r.set_position(0);
+                Ok(())
+            }
+            Self::Limited(r) => {
+                r.get_mut().rewind(limit)?;
+                r.set_limit(limit.unwrap_or(u64::MAX));
+                Ok(())
+            }
+            Self::Stdin(_) | Self::TimedStdin(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "standard input cannot be rewound unless buffered",
+            )),
+            Self::Boxed(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "injected reader cannot be rewound",
+            )),
+        }
+    }
+
+    fn is_stdin(&self) -> bool {
+        match self {
+            Self::Stdin(_) | Self::TimedStdin(_) => true,
+            Self::StdinReplay(_) => true,
+            Self::File(_) | Self::Boxed(_) | Self::Stable(_) => false,
+            Self::Limited(r) => r.get_ref().is_stdin(),
+        }
+    }
+
+    /// Returns the underlying file, if any.
+    fn file(&self) -> Option<&fs::File> {
+        match self {
+            Self::File(r) | Self::Stable(StableFile(r)) => Some(r.get_ref()),
+            Self::Limited(r) => r.get_ref().file(),
+            _ => None,
+        }
+    }
+}
+
+impl io::Read for Reader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.as_buf_read_mut().read(buf)
+    }
+}
+
+impl BufRead for Reader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.as_buf_read_mut().fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.as_buf_read_mut().consume(amount)
+    }
+}
+
+/// A regular file that checks its length on reaching EOF and keeps reading if it has grown.
+struct StableFile(io::BufReader<fs::File>);
+
+impl StableFile {
+    /// Returns whether the file has grown past the current position, failing if it has shrunk.
+    fn has_grown(&mut self) -> io::Result<bool> {
+        let len = self.0.get_ref().metadata()?.len();
+        let pos = io::Seek::stream_position(&mut self.0)?;
+        if len < pos {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file was truncated while reading",
+            ));
+        }
+        Ok(len > pos)
+    }
+}
+
+impl io::Read for StableFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = io::Read::read(&mut self.fill_buf()?, buf)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for StableFile {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.0.fill_buf()?.is_empty() && !self.has_grown()? {
+            return Ok(&[]);
+        }
+        self.0.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.0.consume(amount)
+    }
+}
+
+/// A reader that counts the bytes consumed through it.
+struct CountingReader<'a>(&'a mut dyn BufRead, usize);
+
+impl io::Read for CountingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = io::Read::read(&mut self.fill_buf()?, buf)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for CountingReader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.0.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.0.consume(amount);
+        self.1 += amount;
+    }
+}
+
+/// Standard input read by a background thread so that each read can time out.
+#[derive(Debug)]
+struct TimedStdin {
+    rx: mpsc::Receiver<io::Result<Vec<u8>>>,
+    timeout: time::Duration,
+    buf: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+impl TimedStdin {
+    fn spawn(timeout: time::Duration, capacity: Option<usize>) -> Self {
+        let capacity = capacity.unwrap_or(8 * 1024).max(1);
+        let (tx, rx) = mpsc::sync_channel(1);
+        thread::spawn(move || {
+            // locks standard input only during each read rather than for the lifetime of the thread
+            let mut stdin = io::stdin();
+            loop {
+                let mut chunk = vec![0; capacity];
+                let ret = match io::Read::read(&mut stdin, &mut chunk) {
+                    Ok(n) => {
+                        chunk.truncate(n);
+                        Ok(chunk)
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e),
+                };
+                let done = !matches!(&ret, Ok(chunk) if !chunk.is_empty());
+                if tx.send(ret).is_err() || done {
+                    break;
+                }
+            }
+        });
+        Self {
+            rx,
+            timeout,
+            buf: Vec::new(),
+            pos: 0,
+            eof: false,
+        }
+    }
+}
+
+impl io::Read for TimedStdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.fill_buf()?.read(buf)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for TimedStdin {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.buf.len() && !self.eof {
+            match self.rx.recv_timeout(self.timeout) {
+                Ok(Ok(chunk)) => {
+                    self.eof = chunk.is_empty();
+                    self.buf = chunk;
+                    self.pos = 0;
+                }
+                Ok(Err(e)) => {
+                    self.eof = true;
+                    return Err(e);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "timed out waiting for standard input",
+                    ));
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => self.eof = true,
+            }
         }
+        Ok(&self.buf[self.pos..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.pos = (self.pos + amount).min(self.buf.len());
     }
 }